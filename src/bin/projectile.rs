@@ -1,16 +1,18 @@
 extern crate ray_tracer_challenge;
 
 use ray_tracer_challenge::canvas::Canvas;
-use ray_tracer_challenge::tuple::Tuple;
+use ray_tracer_challenge::color::Color;
+use ray_tracer_challenge::point::Point;
+use ray_tracer_challenge::vector::Vector;
 
 struct Projectile {
-    position: Tuple,
-    velocity: Tuple,
+    position: Point,
+    velocity: Vector,
 }
 
 struct Environment {
-    gravity: Tuple,
-    wind: Tuple,
+    gravity: Vector,
+    wind: Vector,
 }
 
 fn tick(env: &Environment, proj: &Projectile) -> Projectile {
@@ -22,18 +24,18 @@ fn tick(env: &Environment, proj: &Projectile) -> Projectile {
 
 fn main() {
     let env = Environment {
-        gravity: Tuple::vector(0.0, -0.1, 0.0),
-        wind: Tuple::vector(-0.01, 0.0, 0.0),
+        gravity: Vector::new(0.0, -0.1, 0.0),
+        wind: Vector::new(-0.01, 0.0, 0.0),
     };
     let mut proj = Projectile {
-        position: Tuple::point(0.0, 1.0, 0.0),
-        velocity: Tuple::vector(1.0, 1.8, 0.0).normalize() * 11.25,
+        position: Point::new(0.0, 1.0, 0.0),
+        velocity: Vector::new(1.0, 1.8, 0.0).normalize() * 11.25,
     };
 
     let width = 900;
     let height = 550;
     let mut canvas = Canvas::new(width, height);
-    let col = Tuple::color(0.5, 0.5, 0.5);
+    let col = Color::new(0.5, 0.5, 0.5);
 
     while proj.position.1 > 0.0 {
         let x = proj.position.0.round() as usize;
@@ -1,14 +1,16 @@
 extern crate ray_tracer_challenge;
 
 use ray_tracer_challenge::canvas::Canvas;
+use ray_tracer_challenge::color::Color;
 use ray_tracer_challenge::intersection;
 use ray_tracer_challenge::lights::PointLight;
+use ray_tracer_challenge::point::Point;
 use ray_tracer_challenge::ray::Ray;
+use ray_tracer_challenge::shape::Shape;
 use ray_tracer_challenge::sphere::Sphere;
-use ray_tracer_challenge::tuple::Tuple;
 
 fn main() {
-    let ray_origin = Tuple::point(0.0, 0.0, -5.0);
+    let ray_origin = Point::new(0.0, 0.0, -5.0);
     let wall_z = 10.0;
     let wall_size = 7.0;
     let canvas_pixels = 100;
@@ -17,17 +19,17 @@ fn main() {
 
     let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
     let mut shape = Sphere::new();
-    shape.material.color = Tuple::color(1.0, 0.2, 1.0);
+    shape.material.color = Color::new(1.0, 0.2, 1.0);
 
-    let light_position = Tuple::point(-10.0, 10.0, -10.0);
-    let light_color = Tuple::color(1.0, 1.0, 1.0);
+    let light_position = Point::new(-10.0, 10.0, -10.0);
+    let light_color = Color::new(1.0, 1.0, 1.0);
     let light = PointLight::new(light_position, light_color);
 
     for y in 0..canvas_pixels {
         let world_y = half - pixel_size * (y as f64);
         for x in 0..canvas_pixels {
             let world_x = -half + pixel_size * (x as f64);
-            let position = Tuple::point(world_x, world_y, wall_z);
+            let position = Point::new(world_x, world_y, wall_z);
             let r = Ray::new(ray_origin, (position - ray_origin).normalize());
             let xs = shape.intersect(&r);
 
@@ -35,7 +37,10 @@ fn main() {
                 let point = r.position(hit.t);
                 let normal = hit.object.normal_at(point);
                 let eye = -r.direction;
-                let color = hit.object.material.lightning(light, point, eye, normal);
+                let color = hit
+                    .object
+                    .material()
+                    .lightning(hit.object, light, point, eye, normal, false);
                 canvas.write_pixel(x, y, color);
             }
         }
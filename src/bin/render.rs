@@ -0,0 +1,225 @@
+extern crate ray_tracer_challenge;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use ray_tracer_challenge::camera::Camera;
+use ray_tracer_challenge::canvas::Canvas;
+use ray_tracer_challenge::png::BitDepth;
+use ray_tracer_challenge::scene;
+
+const USAGE: &str = "usage: render [options] <scene> <output>
+
+Render a scene file into an image. Use - as output to write to stdout.
+
+options:
+  --width <pixels>     override the width of the camera
+  --height <pixels>    override the height of the camera
+                       (giving only one of them keeps the aspect ratio)
+  --threads <n>        number of render threads (default: all cores)
+  --samples <n>        rays per pixel for antialiasing (default: 1)
+  --format <format>    output format: ppm, ppm-ascii, png, png16, pfm or hdr
+                       (default: from the output name)
+  --help               show this message";
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Ppm,
+    PpmAscii,
+    Png,
+    Png16,
+    Pfm,
+    Hdr,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "ppm" => Some(Format::Ppm),
+            "ppm-ascii" => Some(Format::PpmAscii),
+            "png" => Some(Format::Png),
+            "png16" => Some(Format::Png16),
+            "pfm" => Some(Format::Pfm),
+            "hdr" => Some(Format::Hdr),
+            _ => None,
+        }
+    }
+
+    fn write(self, canvas: &Canvas, out: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Format::Ppm => canvas.write_ppm(out),
+            Format::PpmAscii => canvas.write_ppm_ascii(out),
+            Format::Png => canvas.write_png(out, BitDepth::Eight),
+            Format::Png16 => canvas.write_png(out, BitDepth::Sixteen),
+            Format::Pfm => canvas.write_pfm(out),
+            Format::Hdr => canvas.write_hdr(out),
+        }
+    }
+}
+
+struct Options {
+    scene: String,
+    output: String,
+    width: Option<usize>,
+    height: Option<usize>,
+    threads: usize,
+    samples: usize,
+    format: Format,
+}
+
+/// Failure with the message to show and the exit code to use
+struct Failure(String, i32);
+
+fn usage_error(message: String) -> Failure {
+    Failure(format!("{}\n\n{}", message, USAGE), 2)
+}
+
+fn parse_args(args: &[String]) -> Result<Options, Failure> {
+    let mut positional = vec![];
+    let mut width = None;
+    let mut height = None;
+    let mut threads = None;
+    let mut samples = 1;
+    let mut format = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| usage_error(format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "--help" => return Err(Failure(USAGE.to_string(), 0)),
+            "--width" => width = Some(parse_count("--width", value("--width")?)?),
+            "--height" => height = Some(parse_count("--height", value("--height")?)?),
+            "--threads" => threads = Some(parse_count("--threads", value("--threads")?)?),
+            "--samples" => samples = parse_count("--samples", value("--samples")?)?,
+            "--format" => {
+                let name = value("--format")?;
+                format = Some(
+                    Format::from_name(name)
+                        .ok_or_else(|| usage_error(format!("unknown format '{}'", name)))?,
+                );
+            }
+            a if a.starts_with("--") => return Err(usage_error(format!("unknown option {}", a))),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let (scene, output) = match (positional.next(), positional.next(), positional.next()) {
+        (Some(scene), Some(output), None) => (scene, output),
+        _ => {
+            return Err(usage_error(
+                "expected a scene and an output file".to_string(),
+            ))
+        }
+    };
+    let format = match format {
+        Some(format) => format,
+        None => Path::new(&output)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::from_name)
+            .unwrap_or(Format::Ppm),
+    };
+    let threads = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+
+    Ok(Options {
+        scene,
+        output,
+        width,
+        height,
+        threads,
+        samples,
+        format,
+    })
+}
+
+fn parse_count(option: &str, value: &str) -> Result<usize, Failure> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(usage_error(format!(
+            "{} must be a positive integer, not '{}'",
+            option, value
+        ))),
+    }
+}
+
+/// Camera with the size requested on the command line
+fn resize(camera: &Camera, width: Option<usize>, height: Option<usize>) -> Camera {
+    let aspect = camera.hsize() as f64 / camera.vsize() as f64;
+    let (hsize, vsize) = match (width, height) {
+        (None, None) => return copy_camera(camera, camera.hsize(), camera.vsize()),
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, ((w as f64 / aspect).round() as usize).max(1)),
+        (None, Some(h)) => (((h as f64 * aspect).round() as usize).max(1), h),
+    };
+    copy_camera(camera, hsize, vsize)
+}
+
+fn copy_camera(camera: &Camera, hsize: usize, vsize: usize) -> Camera {
+    let mut c = Camera::new(hsize, vsize, camera.field_of_view());
+    c.set_transform(*camera.transform());
+    c
+}
+
+fn run(options: &Options) -> Result<(), Failure> {
+    let input = fs::read_to_string(&options.scene)
+        .map_err(|e| Failure(format!("cannot read {}: {}", options.scene, e), 1))?;
+    let scene =
+        scene::parse_scene(&input).map_err(|e| Failure(format!("{}: {}", options.scene, e), 1))?;
+
+    // open the output first, so that a bad path fails before a long render
+    let write_error = |e| Failure(format!("cannot write {}: {}", options.output, e), 1);
+    let mut out: Box<dyn Write> = if options.output == "-" {
+        Box::new(io::stdout().lock())
+    } else {
+        let file = fs::File::create(&options.output).map_err(write_error)?;
+        Box::new(io::BufWriter::new(file))
+    };
+
+    let mut camera = resize(&scene.camera, options.width, options.height);
+    camera.set_samples(options.samples);
+
+    let rows = camera.vsize();
+    let shown = AtomicUsize::new(0);
+    let start = Instant::now();
+    let canvas = camera.render_with_progress(&scene.world, options.threads, |done| {
+        let percent = done * 100 / rows;
+        if shown.fetch_max(percent, Ordering::Relaxed) < percent {
+            eprint!("\rrendering {}x{}: {:3}%", camera.hsize(), rows, percent);
+        }
+    });
+    eprintln!(
+        "\nrendered {}x{} in {:.1}s",
+        camera.hsize(),
+        rows,
+        start.elapsed().as_secs_f64()
+    );
+
+    options
+        .format
+        .write(&canvas, &mut out)
+        .and_then(|_| out.flush())
+        .map_err(write_error)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|options| run(&options));
+    if let Err(Failure(message, code)) = result {
+        if code == 0 {
+            println!("{}", message);
+        } else {
+            eprintln!("render: {}", message);
+        }
+        process::exit(code);
+    }
+}
@@ -0,0 +1,398 @@
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::EPSILON;
+
+/// Axis-aligned bounding box.
+///
+/// Unbounded shapes such as planes use infinite extents; an empty box has
+/// `min` above `max` so that adding the first point sets both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    pub fn empty() -> BoundingBox {
+        BoundingBox {
+            min: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn infinite() -> BoundingBox {
+        BoundingBox {
+            min: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.0 > self.max.0 || self.min.1 > self.max.1 || self.min.2 > self.max.2
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|p| p.0.is_finite() && p.1.is_finite() && p.2.is_finite())
+    }
+
+    pub fn add_point(&mut self, point: Point) {
+        self.min = Point::new(
+            self.min.0.min(point.0),
+            self.min.1.min(point.1),
+            self.min.2.min(point.2),
+        );
+        self.max = Point::new(
+            self.max.0.max(point.0),
+            self.max.1.max(point.1),
+            self.max.2.max(point.2),
+        );
+    }
+
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if !other.is_empty() {
+            self.add_point(other.min);
+            self.add_point(other.max);
+        }
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.min.0..=self.max.0).contains(&point.0)
+            && (self.min.1..=self.max.1).contains(&point.1)
+            && (self.min.2..=self.max.2).contains(&point.2)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// Box enclosing this box after `m` has been applied to it. Boxes with
+    /// infinite extents stay infinite in every direction, since a rotation
+    /// can spread an infinite axis onto the others.
+    pub fn transform(&self, m: &Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            return BoundingBox::infinite();
+        }
+
+        let (a, b) = (self.min, self.max);
+        let corners = [
+            Point::new(a.0, a.1, a.2),
+            Point::new(a.0, a.1, b.2),
+            Point::new(a.0, b.1, a.2),
+            Point::new(a.0, b.1, b.2),
+            Point::new(b.0, a.1, a.2),
+            Point::new(b.0, a.1, b.2),
+            Point::new(b.0, b.1, a.2),
+            Point::new(b.0, b.1, b.2),
+        ];
+        let mut result = BoundingBox::empty();
+        for corner in corners.iter() {
+            result.add_point(m * *corner);
+        }
+        result
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let (xtmin, xtmax) = check_axis(ray.origin.0, ray.direction.0, self.min.0, self.max.0);
+        let (ytmin, ytmax) = check_axis(ray.origin.1, ray.direction.1, self.min.1, self.max.1);
+        let (ztmin, ztmax) = check_axis(ray.origin.2, ray.direction.2, self.min.2, self.max.2);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax && tmax >= 0.0
+    }
+
+    /// Split the box in half across its longest axis
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let dx = self.max.0 - self.min.0;
+        let dy = self.max.1 - self.min.1;
+        let dz = self.max.2 - self.min.2;
+        let greatest = dx.max(dy).max(dz);
+
+        let (mut x0, mut y0, mut z0) = (self.min.0, self.min.1, self.min.2);
+        let (mut x1, mut y1, mut z1) = (self.max.0, self.max.1, self.max.2);
+
+        if greatest == dx {
+            x0 += dx / 2.0;
+            x1 = x0;
+        } else if greatest == dy {
+            y0 += dy / 2.0;
+            y1 = y0;
+        } else {
+            z0 += dz / 2.0;
+            z1 = z0;
+        }
+
+        let mid_min = Point::new(x0, y0, z0);
+        let mid_max = Point::new(x1, y1, z1);
+        (
+            BoundingBox::new(self.min, mid_max),
+            BoundingBox::new(mid_min, self.max),
+        )
+    }
+}
+
+/// Entry and exit distance of a ray along one axis of a slab from `min` to
+/// `max`
+pub(crate) fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transformation;
+    use crate::vector::Vector;
+
+    use std::f64::consts::{PI, SQRT_2};
+
+    #[test]
+    fn creating_empty_bounding_box() {
+        let b = BoundingBox::empty();
+
+        assert!(b.is_empty());
+        assert_eq!(f64::INFINITY, b.min.0);
+        assert_eq!(f64::NEG_INFINITY, b.max.0);
+    }
+
+    #[test]
+    fn adding_points_to_empty_bounding_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(Point::new(-5.0, 2.0, 0.0));
+        b.add_point(Point::new(7.0, 0.0, -3.0));
+
+        assert_eq!(Point::new(-5.0, 0.0, -3.0), b.min);
+        assert_eq!(Point::new(7.0, 2.0, 0.0), b.max);
+    }
+
+    #[test]
+    fn adding_one_bounding_box_to_another() {
+        let mut a = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        a.merge(&b);
+
+        assert_eq!(Point::new(-5.0, -7.0, -2.0), a.min);
+        assert_eq!(Point::new(14.0, 4.0, 8.0), a.max);
+    }
+
+    #[test]
+    fn checking_if_box_contains_point() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+
+        assert!(b.contains_point(Point::new(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(Point::new(11.0, 4.0, 7.0)));
+        assert!(b.contains_point(Point::new(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(Point::new(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, -4.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, 1.0, -1.0)));
+        assert!(!b.contains_point(Point::new(13.0, 1.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, 5.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, 1.0, 8.0)));
+    }
+
+    #[test]
+    fn checking_if_box_contains_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let inner = BoundingBox::new(Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0));
+        let outer = BoundingBox::new(Point::new(4.0, -3.0, -1.0), Point::new(10.0, 3.0, 6.0));
+
+        assert!(b.contains_box(&b));
+        assert!(b.contains_box(&inner));
+        assert!(!b.contains_box(&outer));
+    }
+
+    #[test]
+    fn transforming_bounding_box() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let m = transformation::rotation_x(PI / 4.0) * transformation::rotation_y(PI / 4.0);
+        let b2 = b.transform(&m);
+
+        let (a, b) = (SQRT_2, 1.0 + SQRT_2 / 2.0);
+        assert_eq!(Point::new(-a, -b, -b), b2.min);
+        assert_eq!(Point::new(a, b, b), b2.max);
+    }
+
+    #[test]
+    fn transforming_infinite_bounding_box() {
+        let b = BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+        let b2 = b.transform(&transformation::rotation_z(PI / 2.0));
+
+        assert_eq!(f64::NEG_INFINITY, b2.min.1);
+        assert_eq!(f64::INFINITY, b2.max.1);
+    }
+
+    #[test]
+    fn intersecting_ray_with_bounding_box_at_origin() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0), true),
+            (Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0), true),
+            (Point::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), true),
+            (Point::new(0.5, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0), true),
+            (Point::new(0.5, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), true),
+            (Point::new(0.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, 1.0), true),
+            (
+                Point::new(-2.0, 0.0, 0.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(0.0, -2.0, 0.0),
+                Vector::new(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Point::new(0.0, 0.0, -2.0),
+                Vector::new(4.0, 6.0, 2.0),
+                false,
+            ),
+            (
+                Point::new(2.0, 0.0, 2.0),
+                Vector::new(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Point::new(0.0, 2.0, 2.0),
+                Vector::new(0.0, -1.0, 0.0),
+                false,
+            ),
+            (
+                Point::new(2.0, 2.0, 0.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+
+        for (origin, direction, result) in cases.iter() {
+            let r = Ray::new(*origin, direction.normalize());
+            assert_eq!(*result, b.intersects(&r));
+        }
+    }
+
+    #[test]
+    fn intersecting_ray_with_non_cubic_bounding_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (
+                Point::new(15.0, 1.0, 2.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Point::new(-5.0, -1.0, 4.0),
+                Vector::new(1.0, 0.0, 0.0),
+                true,
+            ),
+            (Point::new(7.0, 6.0, 5.0), Vector::new(0.0, -1.0, 0.0), true),
+            (Point::new(9.0, -5.0, 6.0), Vector::new(0.0, 1.0, 0.0), true),
+            (
+                Point::new(8.0, 2.0, 12.0),
+                Vector::new(0.0, 0.0, -1.0),
+                true,
+            ),
+            (Point::new(6.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(8.0, 1.0, 3.5), Vector::new(0.0, 0.0, 1.0), true),
+            (
+                Point::new(9.0, -1.0, -8.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(8.0, 3.0, -4.0),
+                Vector::new(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Point::new(9.0, -1.0, -2.0),
+                Vector::new(4.0, 6.0, 2.0),
+                false,
+            ),
+            (
+                Point::new(4.0, 0.0, 9.0),
+                Vector::new(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Point::new(8.0, 6.0, -1.0),
+                Vector::new(0.0, -1.0, 0.0),
+                false,
+            ),
+            (
+                Point::new(12.0, 5.0, 4.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+
+        for (origin, direction, result) in cases.iter() {
+            let r = Ray::new(*origin, direction.normalize());
+            assert_eq!(*result, b.intersects(&r));
+        }
+    }
+
+    #[test]
+    fn box_behind_ray_is_missed() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(!b.intersects(&r));
+    }
+
+    #[test]
+    fn splitting_box_along_longest_axis() {
+        let b = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(9.0, 5.5, 3.0));
+        let (left, right) = b.split();
+
+        assert_eq!(Point::new(-1.0, -2.0, -3.0), left.min);
+        assert_eq!(Point::new(4.0, 5.5, 3.0), left.max);
+        assert_eq!(Point::new(4.0, -2.0, -3.0), right.min);
+        assert_eq!(Point::new(9.0, 5.5, 3.0), right.max);
+    }
+
+    #[test]
+    fn splitting_wide_box_along_y_and_z() {
+        let b = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(5.0, 8.0, 3.0));
+        let (left, right) = b.split();
+        assert_eq!(Point::new(5.0, 3.0, 3.0), left.max);
+        assert_eq!(Point::new(-1.0, 3.0, -3.0), right.min);
+
+        let b = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(5.0, 3.0, 7.0));
+        let (left, right) = b.split();
+        assert_eq!(Point::new(5.0, 3.0, 2.0), left.max);
+        assert_eq!(Point::new(-1.0, -2.0, 2.0), right.min);
+    }
+}
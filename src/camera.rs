@@ -1,15 +1,340 @@
-use crate::matrix::Matrix;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::transformation::Transform;
+use crate::world::World;
 
 #[derive(Debug)]
 pub struct Camera {
     hsize: usize,
     vsize: usize,
     pixel_size: f64,
+    half_width: f64,
+    half_height: f64,
     field_of_view: f64,
-    transform: Matrix,
+    transform: Transform,
+    samples: usize,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Camera {
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Camera {
+            hsize,
+            vsize,
+            pixel_size: (half_width * 2.0) / hsize as f64,
+            half_width,
+            half_height,
+            field_of_view,
+            transform: Transform::identity(),
+            samples: 1,
+        }
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn transform(&self) -> &Matrix4 {
+        self.transform.matrix()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible, e.g. a view transform
+    /// looking from a point at itself.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    /// Number of rays averaged for every pixel
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    /// Ray through the point (`dx`, `dy`) of the pixel, both in 0..1
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        // offset from the edge of the canvas to the point in the pixel
+        let xoffset = (px as f64 + dx) * self.pixel_size;
+        let yoffset = (py as f64 + dy) * self.pixel_size;
+
+        // the camera looks toward -z, so +x is to the left
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let inv = self.transform.inverse();
+        let pixel = inv * Point::new(world_x, world_y, -1.0);
+        let origin = inv * Point::new(0.0, 0.0, 0.0);
+        let direction = (pixel - origin).normalize();
+
+        Ray::new(origin, direction)
+    }
+
+    /// Color of the pixel, averaged over `samples()` rays spread evenly
+    /// over it. A single sample goes through the center of the pixel.
+    pub fn pixel_color(&self, world: &World, px: usize, py: usize) -> Color {
+        if self.samples == 1 {
+            return world.color_at(&self.ray_for_pixel(px, py));
+        }
+
+        // R2 low discrepancy sequence, see
+        // http://extremelearning.com.au/unreasonable-effectiveness-of-quasirandom-sequences/
+        const A1: f64 = 0.754_877_666_246_692_7;
+        const A2: f64 = 0.569_840_290_998_053_3;
+        let sum = (0..self.samples)
+            .map(|i| {
+                let dx = (0.5 + A1 * i as f64).fract();
+                let dy = (0.5 + A2 * i as f64).fract();
+                world.color_at(&self.ray_for_subpixel(px, py, dx, dy))
+            })
+            .fold(Color::black(), |acc, c| acc + c);
+        sum * (1.0 / self.samples as f64)
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                image.write_pixel(x, y, self.pixel_color(world, x, y));
+            }
+        }
+        image
+    }
+
+    /// Render `world` on `threads` threads. Scanlines are handed out one at
+    /// a time to whichever thread is free, and every pixel is computed
+    /// exactly as in `render`, so the image is identical to the serial one.
+    pub fn render_parallel(&self, world: &World, threads: usize) -> Canvas {
+        if threads <= 1 {
+            return self.render(world);
+        }
+        self.render_with_progress(world, threads, |_| ())
+    }
+
+    /// Like `render_parallel`, but calls `progress` with the number of
+    /// finished scanlines every time one is done
+    pub fn render_with_progress<F>(&self, world: &World, threads: usize, progress: F) -> Canvas
+    where
+        F: Fn(usize) + Sync,
+    {
+        let threads = threads.clamp(1, self.vsize.max(1));
+        let next_row = AtomicUsize::new(0);
+        let finished = AtomicUsize::new(0);
+        let rows: Vec<(usize, Vec<Color>)> = thread::scope(|s| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut rows = vec![];
+                        loop {
+                            let y = next_row.fetch_add(1, Ordering::Relaxed);
+                            if y >= self.vsize {
+                                break;
+                            }
+                            let row = (0..self.hsize)
+                                .map(|x| self.pixel_color(world, x, y))
+                                .collect();
+                            rows.push((y, row));
+                            progress(finished.fetch_add(1, Ordering::Relaxed) + 1);
+                        }
+                        rows
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("render thread panicked"))
+                .collect()
+        });
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for (y, row) in rows {
+            for (x, color) in row.into_iter().enumerate() {
+                image.write_pixel(x, y, color);
+            }
+        }
+        image
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::plane::Plane;
+    use crate::shape::Shape;
+    use crate::transformation;
+    use crate::vector::Vector;
+
+    use std::f64::consts::{PI, SQRT_2};
+
+    #[test]
+    fn constructing_camera() {
+        let c = Camera::new(160, 120, PI / 2.0);
+
+        assert_eq!(160, c.hsize());
+        assert_eq!(120, c.vsize());
+        assert_eq!(PI / 2.0, c.field_of_view());
+        assert_eq!(Matrix4::identity(), *c.transform());
+    }
+
+    #[test]
+    fn pixel_size_for_horizontal_canvas() {
+        let c = Camera::new(200, 125, PI / 2.0);
+
+        assert!((c.pixel_size() - 0.01).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pixel_size_for_vertical_canvas() {
+        let c = Camera::new(125, 200, PI / 2.0);
+
+        assert!((c.pixel_size() - 0.01).abs() < 1e-5);
+    }
+
+    #[test]
+    fn constructing_ray_through_center_of_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+
+        assert_eq!(Point::new(0.0, 0.0, 0.0), r.origin);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), r.direction);
+    }
+
+    #[test]
+    fn constructing_ray_through_corner_of_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+
+        assert_eq!(Point::new(0.0, 0.0, 0.0), r.origin);
+        assert_eq!(Vector::new(0.66519, 0.33259, -0.66851), r.direction);
+    }
+
+    #[test]
+    fn constructing_ray_when_camera_is_transformed() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(
+            transformation::rotation_y(PI / 4.0) * transformation::translation(0.0, -2.0, 5.0),
+        );
+        let r = c.ray_for_pixel(100, 50);
+
+        assert_eq!(Point::new(0.0, 2.0, -5.0), r.origin);
+        assert_eq!(Vector::new(SQRT_2 / 2.0, 0.0, -SQRT_2 / 2.0), r.direction);
+    }
+
+    #[test]
+    fn rendering_world_with_camera() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        c.set_transform(transformation::view_transform(from, to, up));
+        let image = c.render(&w);
+
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), image.pixel_at(5, 5));
+    }
+
+    #[test]
+    fn parallel_render_matches_serial_render() {
+        let mut w = World::default();
+        let mut floor = Plane::new();
+        floor.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        floor.material.reflective = 0.5;
+        w.add_object(floor);
+        let mut c = Camera::new(33, 21, PI / 3.0);
+        c.set_transform(transformation::view_transform(
+            Point::new(0.0, 1.5, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let serial = c.render(&w);
+
+        for threads in [1, 2, 3, 8, 100].iter() {
+            let parallel = c.render_parallel(&w, *threads);
+            for y in 0..c.vsize() {
+                for x in 0..c.hsize() {
+                    let (a, b) = (serial.pixel_at(x, y), parallel.pixel_at(x, y));
+                    assert_eq!((a.0, a.1, a.2), (b.0, b.1, b.2));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_sample_goes_through_pixel_center() {
+        let c = Camera::new(201, 101, PI / 2.0);
+
+        assert_eq!(1, c.samples());
+        assert_eq!(c.ray_for_pixel(3, 7), c.ray_for_subpixel(3, 7, 0.5, 0.5));
+    }
+
+    #[test]
+    fn multiple_samples_average_colors_over_pixel() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(transformation::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c.set_samples(2);
+
+        // the first sample is at the center, the second one is the next
+        // point of the sequence
+        let center = w.color_at(&c.ray_for_subpixel(7, 5, 0.5, 0.5));
+        let other = w.color_at(&c.ray_for_subpixel(7, 5, 0.254_877_666, 0.069_840_291));
+        assert_eq!((center + other) * 0.5, c.pixel_color(&w, 7, 5));
+    }
+
+    #[test]
+    fn there_is_at_least_one_sample() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_samples(0);
+
+        assert_eq!(1, c.samples());
+    }
+
+    #[test]
+    fn progress_is_reported_for_every_row() {
+        let w = World::default();
+        let c = Camera::new(5, 7, PI / 2.0);
+        let reported = std::sync::Mutex::new(vec![]);
+        c.render_with_progress(&w, 3, |n| reported.lock().unwrap().push(n));
+
+        let mut reported = reported.into_inner().unwrap();
+        reported.sort_unstable();
+        assert_eq!((1..=7).collect::<Vec<_>>(), reported);
+    }
 }
@@ -1,14 +1,19 @@
-use crate::tuple::Tuple;
+use std::io::{self, Write};
 
+use crate::color::Color;
+use crate::png::{self, BitDepth, PngError};
+use crate::ppm::{self, PpmError};
+
+#[derive(Debug)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
-    canvas: Vec<Tuple>,
+    canvas: Vec<Color>,
 }
 
 impl Canvas {
     pub fn new(w: usize, h: usize) -> Canvas {
-        let c = vec![Tuple::color(0.0, 0.0, 0.0); w * h];
+        let c = vec![Color::new(0.0, 0.0, 0.0); w * h];
         Canvas {
             width: w,
             height: h,
@@ -16,27 +21,35 @@ impl Canvas {
         }
     }
 
-    pub fn pixel_at(&self, x: usize, y: usize) -> Tuple {
+    pub fn pixel_at(&self, x: usize, y: usize) -> Color {
         self.canvas[x + y * self.width]
     }
 
-    pub fn write_pixel(&mut self, x: usize, y: usize, c: Tuple) {
+    pub fn write_pixel(&mut self, x: usize, y: usize, c: Color) {
         self.canvas[x + y * self.width] = c;
     }
 
+    /// Load a plain (P3) or binary (P6) PPM image
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, PpmError> {
+        ppm::parse(data)
+    }
+
+    /// Load a non-interlaced PNG image, ignoring any alpha channel
+    pub fn from_png(data: &[u8]) -> Result<Canvas, PngError> {
+        png::decode(data)
+    }
+
+    /// Plain (P3) PPM, with lines wrapped to 70 columns
     pub fn to_ppm(&self) -> String {
-        fn ppm_color(x: f64) -> i32 {
-            let v = (x * 255.0).round() as i32;
-            if v < 0 {
-                0
-            } else if v > 255 {
-                255
-            } else {
-                v
-            }
-        }
+        let mut out = vec![];
+        self.write_ppm_ascii(&mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("plain PPM is ASCII")
+    }
 
-        fn colors_to_ppm_string(v: &[i32]) -> String {
+    /// Stream the canvas to `out` as plain (P3) PPM, one row at a time
+    pub fn write_ppm_ascii<W: Write>(&self, mut out: W) -> io::Result<()> {
+        fn colors_to_ppm_string(v: &[u8]) -> String {
             let mut pos = 0;
             let mut s = String::new();
             for i in v {
@@ -55,25 +68,107 @@ impl Canvas {
             s
         }
 
-        // header
-        let mut s = format!("P3\n{} {}\n255\n", self.width, self.height);
+        write!(out, "P3\n{} {}\n255\n", self.width, self.height)?;
+        let mut v = Vec::with_capacity(self.width * 3);
+        for j in 0..self.height {
+            v.clear();
+            self.row_bytes(j, &mut v);
+            writeln!(out, "{}", colors_to_ppm_string(&v))?;
+        }
+        Ok(())
+    }
+
+    /// Stream the canvas to `out` as binary (P6) PPM, one row at a time
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let mut row = Vec::with_capacity(self.width * 3);
+        for j in 0..self.height {
+            row.clear();
+            self.row_bytes(j, &mut row);
+            out.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// PNG image with 8 bits per channel
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self, BitDepth::Eight)
+    }
+
+    /// Write the canvas to `out` as a PNG image with `depth` bits per channel
+    pub fn write_png<W: Write>(&self, mut out: W, depth: BitDepth) -> io::Result<()> {
+        out.write_all(&png::encode(self, depth))
+    }
+
+    /// Stream the canvas to `out` as a Portable FloatMap without clamping.
+    /// Components are little-endian `f32`, with rows stored bottom to top.
+    pub fn write_pfm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        let mut row = Vec::with_capacity(self.width * 12);
+        for j in (0..self.height).rev() {
+            row.clear();
+            for c in &self.canvas[j * self.width..(j + 1) * self.width] {
+                for x in &[c.0, c.1, c.2] {
+                    row.extend_from_slice(&(*x as f32).to_le_bytes());
+                }
+            }
+            out.write_all(&row)?;
+        }
+        Ok(())
+    }
 
-        // content
+    /// Stream the canvas to `out` as an uncompressed Radiance RGBE image.
+    /// Negative components are clamped to zero, and components beyond the
+    /// range of RGBE, about 1.7e38, to its largest value.
+    pub fn write_hdr<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(
+            out,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )?;
+        let mut row = Vec::with_capacity(self.width * 4);
         for j in 0..self.height {
-            let mut v: Vec<i32> = Vec::new();
-            for i in 0..self.width {
-                let c = self.pixel_at(i, j);
-                v.push(ppm_color(c.0));
-                v.push(ppm_color(c.1));
-                v.push(ppm_color(c.2));
+            row.clear();
+            for c in &self.canvas[j * self.width..(j + 1) * self.width] {
+                row.extend_from_slice(&to_rgbe(*c));
             }
-            s.push_str(&colors_to_ppm_string(&v));
-            s.push('\n');
+            out.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Append the 8-bit RGB values of row `y` to `bytes`
+    fn row_bytes(&self, y: usize, bytes: &mut Vec<u8>) {
+        for c in &self.canvas[y * self.width..(y + 1) * self.width] {
+            bytes.extend_from_slice(&[to_byte(c.0), to_byte(c.1), to_byte(c.2)]);
         }
-        s
     }
 }
 
+/// Color component clamped to 0..=1 and scaled to 0..=255
+fn to_byte(x: f64) -> u8 {
+    (x * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Color as three 8-bit mantissas sharing the exponent of the largest component.
+/// Components that are negative or NaN become zero, and those too large for
+/// the 8-bit exponent, including infinity, saturate to the largest value.
+fn to_rgbe(c: Color) -> [u8; 4] {
+    // mantissa 255 with the largest exponent, 127
+    const MAX: f64 = 255.0 / 256.0 * 1.701_411_834_604_692_3e38;
+    let clamp = |x: f64| if x > 0.0 { x.min(MAX) } else { 0.0 };
+    let (r, g, b) = (clamp(c.0), clamp(c.1), clamp(c.2));
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e with 0.5 <= m < 1
+    let e = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(e);
+    let mantissa = |x: f64| (x * scale).min(255.0) as u8;
+    [mantissa(r), mantissa(g), mantissa(b), (e + 128) as u8]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,10 +176,10 @@ mod tests {
     #[test]
     fn creating_canvas() {
         let c = Canvas::new(10, 20);
-        let col = Tuple::color(0.0, 0.0, 0.0);
+        let col = Color::new(0.0, 0.0, 0.0);
 
-        assert_eq!(10, (&c).width);
-        assert_eq!(20, (&c).height);
+        assert_eq!(10, c.width);
+        assert_eq!(20, c.height);
         for i in 0..10 {
             for j in 0..20 {
                 assert_eq!(col, c.pixel_at(i, j));
@@ -95,7 +190,7 @@ mod tests {
     #[test]
     fn writing_pixels_to_canvas() {
         let mut c = Canvas::new(10, 20);
-        let red = Tuple::color(1.0, 0.0, 0.0);
+        let red = Color::new(1.0, 0.0, 0.0);
 
         c.write_pixel(2, 3, red);
 
@@ -112,9 +207,9 @@ mod tests {
     #[test]
     fn constructing_ppm_pixel_data() {
         let mut c = Canvas::new(5, 3);
-        let c1 = Tuple::color(1.5, 0.0, 0.0);
-        let c2 = Tuple::color(0.0, 0.5, 0.0);
-        let c3 = Tuple::color(-0.5, 0.0, 1.0);
+        let c1 = Color::new(1.5, 0.0, 0.0);
+        let c2 = Color::new(0.0, 0.5, 0.0);
+        let c3 = Color::new(-0.5, 0.0, 1.0);
         c.write_pixel(0, 0, c1);
         c.write_pixel(2, 1, c2);
         c.write_pixel(4, 2, c3);
@@ -133,7 +228,7 @@ mod tests {
     #[test]
     fn splitting_long_lines_in_pmm_files() {
         let mut c = Canvas::new(10, 2);
-        let col = Tuple::color(1.0, 0.8, 0.6);
+        let col = Color::new(1.0, 0.8, 0.6);
         for i in 0..10 {
             for j in 0..2 {
                 c.write_pixel(i, j, col);
@@ -159,4 +254,155 @@ mod tests {
         assert_eq!(Some('\n'), ppm.pop());
     }
 
+    #[test]
+    fn streaming_plain_ppm_matches_ppm_string() {
+        let mut c = Canvas::new(10, 2);
+        c.write_pixel(3, 1, Color::new(1.0, 0.8, 0.6));
+        let mut out = vec![];
+        c.write_ppm_ascii(&mut out).unwrap();
+
+        assert_eq!(c.to_ppm().into_bytes(), out);
+    }
+
+    #[test]
+    fn constructing_binary_ppm() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(1.5, 0.5, -0.5));
+        c.write_pixel(0, 1, Color::new(0.0, 0.0, 1.0));
+        let mut out = vec![];
+        c.write_ppm(&mut out).unwrap();
+
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 255, 128, 0, 0, 0, 255, 0, 0, 0]);
+        assert_eq!(expected, out);
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_errors_are_returned() {
+        let c = Canvas::new(2, 2);
+
+        assert_eq!(
+            "disk full",
+            c.write_ppm(FailingWriter).unwrap_err().to_string()
+        );
+        assert!(c.write_ppm_ascii(FailingWriter).is_err());
+    }
+
+    #[test]
+    fn writing_png() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(1, 1, Color::new(1.0, 0.5, 0.0));
+        let mut out = vec![];
+        c.write_png(&mut out, BitDepth::Sixteen).unwrap();
+
+        assert!(out.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(c.to_png().len() < out.len());
+        assert_eq!(png::encode(&c, BitDepth::Sixteen), out);
+        assert_eq!(
+            Color::new(1.0, 0.5, 0.0),
+            png::decode(&out).unwrap().pixel_at(1, 1)
+        );
+    }
+
+    #[test]
+    fn loading_written_ppm_files() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.2, 0.0));
+        c.write_pixel(2, 1, Color::new(0.4, 0.6, 0.8));
+        let mut binary = vec![];
+        c.write_ppm(&mut binary).unwrap();
+
+        for data in [c.to_ppm().into_bytes(), binary].iter() {
+            let loaded = Canvas::from_ppm(data).unwrap();
+            assert_eq!(3, loaded.width);
+            assert_eq!(2, loaded.height);
+            for y in 0..2 {
+                for x in 0..3 {
+                    assert_eq!(c.pixel_at(x, y), loaded.pixel_at(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn loading_written_png() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(0.2, 0.4, 1.0));
+        let loaded = Canvas::from_png(&c.to_png()).unwrap();
+
+        assert_eq!(Color::new(0.2, 0.4, 1.0), loaded.pixel_at(1, 0));
+        assert_eq!(Color::black(), loaded.pixel_at(0, 1));
+    }
+
+    #[test]
+    fn constructing_pfm() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(1.5, 0.5, -0.5));
+        c.write_pixel(0, 1, Color::new(0.0, 0.0, 100.0));
+        let mut out = vec![];
+        c.write_pfm(&mut out).unwrap();
+
+        let mut expected = b"PF\n2 2\n-1.0\n".to_vec();
+        for x in &[
+            0.0f32, 0.0, 100.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.5, 0.5, -0.5,
+        ] {
+            expected.extend_from_slice(&x.to_le_bytes());
+        }
+        assert_eq!(expected, out);
+    }
+
+    #[test]
+    fn constructing_radiance_hdr() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(3.0, 1.5, 0.0));
+        c.write_pixel(0, 1, Color::new(1.0, 0.5, -0.25));
+        let mut out = vec![];
+        c.write_hdr(&mut out).unwrap();
+
+        let mut expected = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 2\n".to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 0, 192, 96, 0, 130, 128, 64, 0, 129, 0, 0, 0, 0]);
+        assert_eq!(expected, out);
+    }
+
+    #[test]
+    fn rgbe_saturates_values_out_of_range() {
+        let max = [255, 255, 255, 255];
+
+        assert_eq!(max, to_rgbe(Color::new(f64::INFINITY, 1e40, 1e300)));
+        assert_eq!(
+            [255, 0, 0, 255],
+            to_rgbe(Color::new(f64::INFINITY, f64::NAN, f64::NEG_INFINITY))
+        );
+        assert_eq!([255, 0, 0, 255], to_rgbe(Color::new(1e40, 1.0, 0.0)));
+        assert_eq!(
+            [128, 0, 0, 255],
+            to_rgbe(Color::new(2f64.powi(126), 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn rgbe_keeps_precision_of_large_and_small_values() {
+        let decode = |rgbe: [u8; 4]| {
+            let scale = 2f64.powi(rgbe[3] as i32 - 136);
+            [rgbe[0], rgbe[1], rgbe[2]].map(|m| (m as f64 + 0.5) * scale)
+        };
+
+        for &v in &[1e-6, 0.3, 1.0, 7.25, 12345.0] {
+            let [r, g, _] = decode(to_rgbe(Color::new(v, v / 2.0, 0.0)));
+            assert!((r - v).abs() / v < 1.0 / 128.0);
+            assert!((g - v / 2.0).abs() / v < 1.0 / 128.0);
+        }
+    }
 }
@@ -0,0 +1,129 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+/// RGB color, nominally with components between 0 and 1
+#[derive(Copy, Clone, Debug)]
+pub struct Color(pub f64, pub f64, pub f64);
+
+impl Color {
+    pub fn new(red: f64, green: f64, blue: f64) -> Color {
+        Color(red, green, blue)
+    }
+
+    pub fn black() -> Color {
+        Color(0.0, 0.0, 0.0)
+    }
+
+    pub fn white() -> Color {
+        Color(1.0, 1.0, 1.0)
+    }
+}
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Color) -> bool {
+        (self.0 - other.0).abs() < EPSILON
+            && (self.1 - other.1).abs() < EPSILON
+            && (self.2 - other.2).abs() < EPSILON
+    }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        Color(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f64) -> Color {
+        Color(self.0 * rhs, self.1 * rhs, self.2 * rhs)
+    }
+}
+
+/// Hadamard product, i.e. component-wise multiplication
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color(self.0 * other.0, self.1 * other.1, self.2 * other.2)
+    }
+}
+
+impl From<Color> for Tuple {
+    fn from(c: Color) -> Tuple {
+        Tuple::color(c.0, c.1, c.2)
+    }
+}
+
+/// Unlike points and vectors, colors do not check `w`: color tuples have
+/// w = 0 like vectors, so `w` says nothing about whether a tuple is a color,
+/// and only the first three components are used
+impl From<Tuple> for Color {
+    fn from(t: Tuple) -> Color {
+        Color(t.0, t.1, t.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_red_green_blue_tuples() {
+        let c = Color::new(-0.5, 0.4, 1.7);
+
+        assert_eq!(-0.5, c.0);
+        assert_eq!(0.4, c.1);
+        assert_eq!(1.7, c.2);
+    }
+
+    #[test]
+    fn adding_colors() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.25);
+
+        assert_eq!(Color::new(1.6, 0.7, 1.0), c1 + c2);
+    }
+
+    #[test]
+    fn subtracting_colors() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.25);
+
+        assert_eq!(Color::new(0.2, 0.5, 0.5), c1 - c2);
+    }
+
+    #[test]
+    fn multiplying_color_by_scalar() {
+        assert_eq!(Color::new(0.4, 0.6, 0.8), Color::new(0.2, 0.3, 0.4) * 2.0);
+    }
+
+    #[test]
+    fn multiplying_colors() {
+        let c1 = Color::new(1.0, 0.2, 0.4);
+        let c2 = Color::new(0.9, 1.0, 0.1);
+
+        assert_eq!(Color::new(0.9, 0.2, 0.04), c1 * c2);
+    }
+
+    #[test]
+    fn converting_color_to_and_from_tuple() {
+        let t: Tuple = Color::new(0.1, 0.2, 0.3).into();
+
+        assert_eq!(Tuple::color(0.1, 0.2, 0.3), t);
+        assert_eq!(Color::new(0.1, 0.2, 0.3), Color::from(t));
+    }
+}
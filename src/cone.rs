@@ -0,0 +1,265 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::cylinder::check_cap;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+use crate::EPSILON;
+
+/// Double-napped cone around the y axis with its apex at the origin,
+/// optionally truncated to `minimum`..`maximum` and capped when `closed`
+#[derive(Debug, PartialEq)]
+pub struct Cone {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new() -> Cone {
+        Cone {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.1.abs() < EPSILON {
+            return;
+        }
+
+        // the radius of a cone's cap is the absolute y value of the cap
+        for &y in [self.minimum, self.maximum].iter() {
+            let t = (y - ray.origin.1) / ray.direction.1;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        let (o, d) = (ray.origin, ray.direction);
+        let a = d.0 * d.0 - d.1 * d.1 + d.2 * d.2;
+        let b = 2.0 * o.0 * d.0 - 2.0 * o.1 * d.1 + 2.0 * o.2 * d.2;
+        let c = o.0 * o.0 - o.1 * o.1 + o.2 * o.2;
+
+        if a.abs() < EPSILON {
+            // the ray is parallel to one of the cone's halves
+            if b.abs() >= EPSILON {
+                let t = -c / (2.0 * b);
+                let y = o.1 + t * d.1;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        } else {
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return xs;
+            }
+
+            let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for &t in [t0, t1].iter() {
+                let y = o.1 + t * d.1;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Point::new(-limit, self.minimum, -limit),
+            Point::new(limit, self.maximum, limit),
+        )
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.0 * point.0 + point.2 * point.2;
+
+        if dist < point.1 * point.1 && point.1 >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < point.1 * point.1 && point.1 <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            let mut y = dist.sqrt();
+            if point.1 > 0.0 {
+                y = -y;
+            }
+            Vector::new(point.0, y, point.2)
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Cone {
+        Cone::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn intersecting_cone_with_ray() {
+        let shape = Cone::new();
+        let cases = [
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 5.0, 5.0),
+            ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0), 8.66025, 8.66025),
+            ((1.0, 1.0, -5.0), (-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), t0, t1) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = shape.local_intersect(&r);
+
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].t).abs() < 1e-4);
+            assert!((t1 - xs[1].t).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn intersecting_cone_with_ray_parallel_to_one_of_its_halves() {
+        let shape = Cone::new();
+        let direction = Vector::new(0.0, 1.0, 1.0).normalize();
+        let r = Ray::new(Point::new(0.0, 0.0, -1.0), direction);
+        let xs = shape.local_intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert!((0.35355 - xs[0].t).abs() < EPSILON);
+    }
+
+    #[test]
+    fn intersecting_cones_end_caps() {
+        let mut shape = Cone::new();
+        shape.minimum = -0.5;
+        shape.maximum = 0.5;
+        shape.closed = true;
+        let cases = [
+            ((0.0, 0.0, -5.0), (0.0, 1.0, 0.0), 0),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 1.0), 2),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 0.0), 4),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), count) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = shape.local_intersect(&r);
+
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn computing_normal_vector_on_cone() {
+        let shape = Cone::new();
+        let cases = [
+            ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+            ((1.0, 1.0, 1.0), (1.0, -f64::sqrt(2.0), 1.0)),
+            ((-1.0, -1.0, 0.0), (-1.0, 1.0, 0.0)),
+        ];
+        for &((px, py, pz), (nx, ny, nz)) in cases.iter() {
+            let n = shape.local_normal_at(Point::new(px, py, pz));
+
+            assert_eq!(Vector::new(nx, ny, nz), n);
+        }
+    }
+
+    #[test]
+    fn cone_in_world() {
+        let mut w = World::new();
+        let mut shape = Cone::new();
+        shape.minimum = -1.0;
+        shape.maximum = 0.0;
+        shape.closed = true;
+        w.add_object(shape);
+        let r = Ray::new(Point::new(0.0, -0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = w.intersect(&r);
+        assert_eq!(2, xs.len());
+        assert!((4.5 - xs[0].t).abs() < EPSILON);
+        assert!((5.5 - xs[1].t).abs() < EPSILON);
+    }
+
+    #[test]
+    fn unbounded_cone_has_bounding_box() {
+        let b = Cone::new().bounds();
+
+        assert!(!b.is_finite());
+        assert_eq!(f64::NEG_INFINITY, b.min.1);
+        assert_eq!(f64::INFINITY, b.max.1);
+    }
+
+    #[test]
+    fn bounded_cone_has_bounding_box() {
+        let mut shape = Cone::new();
+        shape.minimum = -5.0;
+        shape.maximum = 3.0;
+        let b = shape.bounds();
+
+        assert_eq!(Point::new(-5.0, -5.0, -5.0), b.min);
+        assert_eq!(Point::new(5.0, 3.0, 5.0), b.max);
+    }
+}
@@ -0,0 +1,383 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+
+/// How the two operands of a `Csg` are combined
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    /// Everything in either shape
+    Union,
+    /// Only what is in both shapes
+    Intersection,
+    /// The left shape with the right one carved out of it
+    Difference,
+}
+
+/// Constructive solid geometry: two shapes combined with an `Operation`.
+///
+/// Like a `Group`, the transform of the node applies to both operands and
+/// is changed through `set_transform`. Hits keep pointing at the operand
+/// that was hit, so it is the operands' materials that are shaded.
+#[derive(Debug)]
+pub struct Csg {
+    pub operation: Operation,
+    transform: Transform,
+    pub material: Material,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+    parent: Option<Arc<Parent>>,
+    bounds: BoundingBox,
+}
+
+impl Csg {
+    pub fn new<L: Shape, R: Shape>(operation: Operation, left: L, right: R) -> Csg {
+        Csg::from_boxed(operation, Box::new(left), Box::new(right))
+    }
+
+    pub fn from_boxed(operation: Operation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Csg {
+        let mut bounds = left.parent_space_bounds();
+        bounds.merge(&right.parent_space_bounds());
+        let mut csg = Csg {
+            operation,
+            transform: Transform::identity(),
+            material: Material::new(),
+            left,
+            right,
+            parent: None,
+            bounds,
+        };
+        csg.update_operands();
+        csg
+    }
+
+    pub fn union<L: Shape, R: Shape>(left: L, right: R) -> Csg {
+        Csg::new(Operation::Union, left, right)
+    }
+
+    pub fn intersection<L: Shape, R: Shape>(left: L, right: R) -> Csg {
+        Csg::new(Operation::Intersection, left, right)
+    }
+
+    pub fn difference<L: Shape, R: Shape>(left: L, right: R) -> Csg {
+        Csg::new(Operation::Difference, left, right)
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Keep the intersections that lie on the surface of the combined shape.
+    /// `xs` must be sorted.
+    pub fn filter_intersections<'a>(&self, xs: &[Intersection<'a>]) -> Vec<Intersection<'a>> {
+        // both rays start outside of the operands
+        let mut inl = false;
+        let mut inr = false;
+        let mut result = vec![];
+
+        for x in xs {
+            let lhit = self.left.includes(x.object);
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(*x);
+            }
+
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+
+    fn update_operands(&mut self) {
+        let node = Arc::new(Parent {
+            transform: self.transform.clone(),
+            parent: self.parent.clone(),
+        });
+        self.left.set_parent(Some(node.clone()));
+        self.right.set_parent(Some(node));
+    }
+}
+
+/// Whether a hit on the left (`lhit`) or right operand is part of the
+/// surface of the combined shape, given whether the ray is currently inside
+/// the left (`inl`) and the right (`inr`) operand
+pub fn intersection_allowed(op: Operation, lhit: bool, inl: bool, inr: bool) -> bool {
+    match op {
+        Operation::Union => (lhit && !inr) || (!lhit && !inl),
+        Operation::Intersection => (lhit && inr) || (!lhit && inl),
+        Operation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+impl PartialEq for Csg {
+    // the bounds follow from the operands, and may be infinite
+    fn eq(&self, other: &Csg) -> bool {
+        self.operation == other.operation
+            && self.transform == other.transform
+            && self.material == other.material
+            && self.left.as_ref() == other.left.as_ref()
+            && self.right.as_ref() == other.right.as_ref()
+            && self.parent == other.parent
+    }
+}
+
+impl Shape for Csg {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+        self.update_operands();
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+        self.update_operands();
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds.intersects(ray) {
+            return vec![];
+        }
+
+        let mut xs = self.left.intersect(ray);
+        xs.extend(self.right.intersect(ray));
+        self.filter_intersections(&intersection::intersections(&xs))
+    }
+
+    /// # Panics
+    ///
+    /// Always, since a CSG node has no surface of its own. Intersections
+    /// always refer to the operand that was hit, so its normal is used instead.
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("CSG nodes have no surface; normals come from their operands")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.left.divide(threshold);
+        self.right.divide(threshold);
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.left.includes(other) || self.right.includes(other)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::lights::PointLight;
+    use crate::sphere::Sphere;
+    use crate::transformation;
+    use crate::world::World;
+
+    #[test]
+    fn csg_is_created_with_operation_and_two_shapes() {
+        let c = Csg::union(Sphere::new(), Cube::new());
+
+        assert_eq!(Operation::Union, c.operation);
+        assert!(c.left().as_any().is::<Sphere>());
+        assert!(c.right().as_any().is::<Cube>());
+        assert!(c.left().parent().is_some());
+        assert!(c.right().parent().is_some());
+    }
+
+    #[test]
+    fn evaluating_rule_for_csg_operation() {
+        use Operation::*;
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+
+        for &(op, lhit, inl, inr, result) in cases.iter() {
+            assert_eq!(result, intersection_allowed(op, lhit, inl, inr));
+        }
+    }
+
+    #[test]
+    fn filtering_list_of_intersections() {
+        let cases = [
+            (Operation::Union, 0, 3),
+            (Operation::Intersection, 1, 2),
+            (Operation::Difference, 0, 1),
+        ];
+
+        for &(op, x0, x1) in cases.iter() {
+            let c = Csg::new(op, Sphere::new(), Cube::new());
+            let xs = [
+                Intersection::new(1.0, c.left()),
+                Intersection::new(2.0, c.right()),
+                Intersection::new(3.0, c.left()),
+                Intersection::new(4.0, c.right()),
+            ];
+            let result = c.filter_intersections(&xs);
+
+            assert_eq!(2, result.len());
+            assert_eq!(xs[x0], result[0]);
+            assert_eq!(xs[x1], result[1]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_csg_object() {
+        let c = Csg::union(Sphere::new(), Cube::new());
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(c.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_hits_csg_object() {
+        let mut s2 = Sphere::new();
+        s2.set_transform(transformation::translation(0.0, 0.0, 0.5));
+        let c = Csg::union(Sphere::new(), s2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.local_intersect(&r);
+
+        assert_eq!(2, xs.len());
+        assert_eq!(4.0, xs[0].t);
+        assert!(ptr_eq(xs[0].object, c.left()));
+        assert_eq!(6.5, xs[1].t);
+        assert!(ptr_eq(xs[1].object, c.right()));
+    }
+
+    fn ptr_eq(a: &dyn Shape, b: &dyn Shape) -> bool {
+        std::ptr::addr_eq(a, b)
+    }
+
+    #[test]
+    fn csg_includes_shapes_in_nested_nodes() {
+        let inner = Csg::difference(Cube::new(), Sphere::new());
+        let c = Csg::union(inner, Sphere::new());
+        let other = Sphere::new();
+
+        let nested = c.left().as_any().downcast_ref::<Csg>().unwrap().right();
+        assert!(c.includes(nested));
+        assert!(c.includes(c.right()));
+        assert!(!c.includes(&other));
+    }
+
+    #[test]
+    fn hit_on_difference_is_on_carved_surface() {
+        // a cube with a sphere carved out of its front face
+        let mut hole = Sphere::new();
+        hole.set_transform(
+            transformation::translation(0.0, 0.0, -1.0) * transformation::scaling(0.5, 0.5, 0.5),
+        );
+        let c = Csg::difference(Cube::new(), hole);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        let hit = intersection::hit(&xs).unwrap();
+
+        assert_eq!(4.5, hit.t);
+        assert!(ptr_eq(hit.object, c.right()));
+        let comps = hit.prepare_computations(&r, &xs);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
+    }
+
+    #[test]
+    fn lens_from_intersection_of_spheres() {
+        let mut a = Sphere::new();
+        a.set_transform(transformation::translation(0.0, 0.0, 0.5));
+        let mut b = Sphere::new();
+        b.set_transform(transformation::translation(0.0, 0.0, -0.5));
+        let c = Csg::intersection(a, b);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+
+        assert_eq!(2, xs.len());
+        assert_eq!(4.5, xs[0].t);
+        assert_eq!(5.5, xs[1].t);
+    }
+
+    #[test]
+    fn transformed_csg_in_world() {
+        let mut c = Csg::union(Sphere::new(), Cube::new());
+        c.set_transform(transformation::translation(0.0, 0.0, 3.0));
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(c);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+
+        assert_eq!(2, xs.len());
+        assert_eq!(7.0, xs[0].t);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
+    }
+
+    #[test]
+    #[should_panic(expected = "CSG nodes have no surface")]
+    fn csg_has_no_normal_of_its_own() {
+        Csg::union(Sphere::new(), Cube::new()).normal_at(Point::new(1.0, 0.0, 0.0));
+    }
+}
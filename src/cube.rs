@@ -0,0 +1,207 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::{check_axis, BoundingBox};
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+
+/// Axis-aligned cube spanning -1..1 on each axis in object space
+#[derive(Debug, PartialEq)]
+pub struct Cube {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+}
+
+impl Cube {
+    pub fn new() -> Cube {
+        Cube {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+        }
+    }
+}
+
+impl Shape for Cube {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (xtmin, xtmax) = check_axis(ray.origin.0, ray.direction.0, -1.0, 1.0);
+        let (ytmin, ytmax) = check_axis(ray.origin.1, ray.direction.1, -1.0, 1.0);
+        let (ztmin, ztmax) = check_axis(ray.origin.2, ray.direction.2, -1.0, 1.0);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            vec![]
+        } else {
+            vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let maxc = point.0.abs().max(point.1.abs()).max(point.2.abs());
+
+        if maxc == point.0.abs() {
+            Vector::new(point.0, 0.0, 0.0)
+        } else if maxc == point.1.abs() {
+            Vector::new(0.0, point.1, 0.0)
+        } else {
+            Vector::new(0.0, 0.0, point.2)
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+impl Default for Cube {
+    fn default() -> Cube {
+        Cube::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transformation;
+    use crate::world::World;
+
+    use std::f64::consts::PI;
+
+    #[test]
+    fn ray_intersects_cube() {
+        let c = Cube::new();
+        let cases = [
+            // +x, -x, +y, -y, +z, -z, inside
+            ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), 4.0, 6.0),
+            ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), 4.0, 6.0),
+            ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), 4.0, 6.0),
+            ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), 4.0, 6.0),
+            ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), 4.0, 6.0),
+            ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+            ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), t1, t2) in cases.iter() {
+            let r = Ray::new(Point::new(ox, oy, oz), Vector::new(dx, dy, dz));
+            let xs = c.local_intersect(&r);
+
+            assert_eq!(2, xs.len());
+            assert_eq!(t1, xs[0].t);
+            assert_eq!(t2, xs[1].t);
+        }
+    }
+
+    #[test]
+    fn ray_misses_cube() {
+        let c = Cube::new();
+        let cases = [
+            ((-2.0, 0.0, 0.0), (0.2673, 0.5345, 0.8018)),
+            ((0.0, -2.0, 0.0), (0.8018, 0.2673, 0.5345)),
+            ((0.0, 0.0, -2.0), (0.5345, 0.8018, 0.2673)),
+            ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0)),
+            ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0)),
+            ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz)) in cases.iter() {
+            let r = Ray::new(Point::new(ox, oy, oz), Vector::new(dx, dy, dz));
+            let xs = c.local_intersect(&r);
+
+            assert_eq!(0, xs.len());
+        }
+    }
+
+    #[test]
+    fn normal_on_surface_of_cube() {
+        let c = Cube::new();
+        let cases = [
+            ((1.0, 0.5, -0.8), (1.0, 0.0, 0.0)),
+            ((-1.0, -0.2, 0.9), (-1.0, 0.0, 0.0)),
+            ((-0.4, 1.0, -0.1), (0.0, 1.0, 0.0)),
+            ((0.3, -1.0, -0.7), (0.0, -1.0, 0.0)),
+            ((-0.6, 0.3, 1.0), (0.0, 0.0, 1.0)),
+            ((0.4, 0.4, -1.0), (0.0, 0.0, -1.0)),
+            ((1.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+            ((-1.0, -1.0, -1.0), (-1.0, 0.0, 0.0)),
+        ];
+        for &((px, py, pz), (nx, ny, nz)) in cases.iter() {
+            let normal = c.local_normal_at(Point::new(px, py, pz));
+
+            assert_eq!(Vector::new(nx, ny, nz), normal);
+        }
+    }
+
+    #[test]
+    fn normal_on_transformed_cube() {
+        let mut c = Cube::new();
+        c.set_transform(
+            transformation::rotation_y(PI / 4.0) * transformation::scaling(1.0, 2.0, 1.0),
+        );
+        let half = f64::sqrt(2.0) / 2.0;
+        let n = c.normal_at(Point::new(half, 0.5, half));
+
+        assert_eq!(Vector::new(half, 0.0, half), n);
+    }
+
+    #[test]
+    fn cube_in_world() {
+        let mut w = World::new();
+        let mut c = Cube::new();
+        c.set_transform(transformation::translation(0.0, 0.0, 2.0));
+        w.add_object(c);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = w.intersect(&r);
+        assert_eq!(2, xs.len());
+        assert_eq!(6.0, xs[0].t);
+        assert_eq!(8.0, xs[1].t);
+    }
+
+    #[test]
+    fn cube_has_bounding_box() {
+        let b = Cube::new().bounds();
+
+        assert_eq!(Point::new(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(Point::new(1.0, 1.0, 1.0), b.max);
+    }
+}
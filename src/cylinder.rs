@@ -0,0 +1,311 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+use crate::EPSILON;
+
+/// Cylinder of radius 1 around the y axis, optionally truncated to
+/// `minimum`..`maximum` and capped at both ends when `closed`
+#[derive(Debug, PartialEq)]
+pub struct Cylinder {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new() -> Cylinder {
+        Cylinder {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        // caps only matter if the cylinder is closed and might be intersected
+        if !self.closed || ray.direction.1.abs() < EPSILON {
+            return;
+        }
+
+        for &y in [self.minimum, self.maximum].iter() {
+            let t = (y - ray.origin.1) / ray.direction.1;
+            if check_cap(ray, t, 1.0) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+/// Is the intersection at `t` within `radius` of the y axis?
+pub(crate) fn check_cap(ray: &Ray, t: f64, radius: f64) -> bool {
+    let x = ray.origin.0 + t * ray.direction.0;
+    let z = ray.origin.2 + t * ray.direction.2;
+    x * x + z * z <= radius * radius + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        let a = ray.direction.0 * ray.direction.0 + ray.direction.2 * ray.direction.2;
+
+        // a ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.0 * ray.direction.0 + 2.0 * ray.origin.2 * ray.direction.2;
+            let c = ray.origin.0 * ray.origin.0 + ray.origin.2 * ray.origin.2 - 1.0;
+            let disc = b * b - 4.0 * a * c;
+
+            if disc < 0.0 {
+                return xs;
+            }
+
+            let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for &t in [t0, t1].iter() {
+                let y = ray.origin.1 + t * ray.direction.1;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-1.0, self.minimum, -1.0),
+            Point::new(1.0, self.maximum, 1.0),
+        )
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.0 * point.0 + point.2 * point.2;
+
+        if dist < 1.0 && point.1 >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && point.1 <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            Vector::new(point.0, 0.0, point.2)
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Cylinder {
+        Cylinder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_misses_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0)),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz)) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = cyl.local_intersect(&r);
+
+            assert_eq!(0, xs.len());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, -5.0), (0.0, 0.0, 1.0), 5.0, 5.0),
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+            ((0.5, 0.0, -5.0), (0.1, 1.0, 1.0), 6.80798, 7.08872),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), t0, t1) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = cyl.local_intersect(&r);
+
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].t).abs() < EPSILON);
+            assert!((t1 - xs[1].t).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+            ((0.0, 5.0, -1.0), (0.0, 0.0, -1.0)),
+            ((0.0, -2.0, 1.0), (0.0, 0.0, 1.0)),
+            ((-1.0, 1.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+        for &((px, py, pz), (nx, ny, nz)) in cases.iter() {
+            let n = cyl.local_normal_at(Point::new(px, py, pz));
+
+            assert_eq!(Vector::new(nx, ny, nz), n);
+        }
+    }
+
+    #[test]
+    fn default_minimum_and_maximum_for_cylinder() {
+        let cyl = Cylinder::new();
+
+        assert_eq!(f64::NEG_INFINITY, cyl.minimum);
+        assert_eq!(f64::INFINITY, cyl.maximum);
+    }
+
+    #[test]
+    fn intersecting_constrained_cylinder() {
+        let mut cyl = Cylinder::new();
+        cyl.minimum = 1.0;
+        cyl.maximum = 2.0;
+        let cases = [
+            ((0.0, 1.5, 0.0), (0.1, 1.0, 0.0), 0),
+            ((0.0, 3.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 2.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 1.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 1.5, -2.0), (0.0, 0.0, 1.0), 2),
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), count) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = cyl.local_intersect(&r);
+
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn default_closed_value_for_cylinder() {
+        let cyl = Cylinder::new();
+
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn intersecting_caps_of_closed_cylinder() {
+        let mut cyl = Cylinder::new();
+        cyl.minimum = 1.0;
+        cyl.maximum = 2.0;
+        cyl.closed = true;
+        let cases = [
+            ((0.0, 3.0, 0.0), (0.0, -1.0, 0.0), 2),
+            ((0.0, 3.0, -2.0), (0.0, -1.0, 2.0), 2),
+            ((0.0, 4.0, -2.0), (0.0, -1.0, 1.0), 2), // corner case
+            ((0.0, 0.0, -2.0), (0.0, 1.0, 2.0), 2),
+            ((0.0, -1.0, -2.0), (0.0, 1.0, 1.0), 2), // corner case
+        ];
+        for &((ox, oy, oz), (dx, dy, dz), count) in cases.iter() {
+            let direction = Vector::new(dx, dy, dz).normalize();
+            let r = Ray::new(Point::new(ox, oy, oz), direction);
+            let xs = cyl.local_intersect(&r);
+
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_cylinders_end_caps() {
+        let mut cyl = Cylinder::new();
+        cyl.minimum = 1.0;
+        cyl.maximum = 2.0;
+        cyl.closed = true;
+        let cases = [
+            ((0.0, 1.0, 0.0), (0.0, -1.0, 0.0)),
+            ((0.5, 1.0, 0.0), (0.0, -1.0, 0.0)),
+            ((0.0, 1.0, 0.5), (0.0, -1.0, 0.0)),
+            ((0.0, 2.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.5, 2.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 2.0, 0.5), (0.0, 1.0, 0.0)),
+        ];
+        for &((px, py, pz), (nx, ny, nz)) in cases.iter() {
+            let n = cyl.local_normal_at(Point::new(px, py, pz));
+
+            assert_eq!(Vector::new(nx, ny, nz), n);
+        }
+    }
+
+    #[test]
+    fn unbounded_cylinder_has_bounding_box() {
+        let b = Cylinder::new().bounds();
+
+        assert_eq!(-1.0, b.min.0);
+        assert_eq!(f64::NEG_INFINITY, b.min.1);
+        assert_eq!(-1.0, b.min.2);
+        assert_eq!(1.0, b.max.0);
+        assert_eq!(f64::INFINITY, b.max.1);
+        assert_eq!(1.0, b.max.2);
+    }
+
+    #[test]
+    fn bounded_cylinder_has_bounding_box() {
+        let mut cyl = Cylinder::new();
+        cyl.minimum = -5.0;
+        cyl.maximum = 3.0;
+        let b = cyl.bounds();
+
+        assert_eq!(Point::new(-1.0, -5.0, -1.0), b.min);
+        assert_eq!(Point::new(1.0, 3.0, 1.0), b.max);
+    }
+}
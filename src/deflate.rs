@@ -0,0 +1,633 @@
+use std::error::Error;
+use std::fmt;
+
+/// How `zlib_compress` encodes the data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    /// Uncompressed blocks, fast but as large as the input
+    Stored,
+    /// LZ77 with the fixed Huffman codes of the deflate format
+    Compressed,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InflateError {
+    UnexpectedEnd,
+    InvalidHeader,
+    InvalidBlockType,
+    InvalidStoredLength,
+    InvalidCode,
+    InvalidDistance,
+    ChecksumMismatch,
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            InflateError::UnexpectedEnd => "compressed data ends unexpectedly",
+            InflateError::InvalidHeader => "invalid zlib header",
+            InflateError::InvalidBlockType => "invalid deflate block type",
+            InflateError::InvalidStoredLength => "invalid length of stored block",
+            InflateError::InvalidCode => "invalid Huffman code",
+            InflateError::InvalidDistance => "distance points before start of data",
+            InflateError::ChecksumMismatch => "checksum of decompressed data does not match",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for InflateError {}
+
+/// Length of a match is `LENGTH_BASE[i]` plus `LENGTH_EXTRA[i]` extra bits
+/// for length code 257 + i
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which the code lengths of the code length alphabet are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried for a match
+const MAX_CHAIN: usize = 128;
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before b may overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Wrap `data` in a zlib stream
+pub fn zlib_compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    let mut out = BitWriter::new();
+    match compression {
+        Compression::Stored => {
+            out.bytes.extend_from_slice(&[0x78, 0x01]);
+            write_stored(&mut out, data);
+        }
+        Compression::Compressed => {
+            out.bytes.extend_from_slice(&[0x78, 0x9c]);
+            write_fixed(&mut out, data);
+        }
+    }
+    out.align();
+    out.bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    out.bytes
+}
+
+/// Decompress a zlib stream, checking its checksum
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, InflateError> {
+    if data.len() < 2 {
+        return Err(InflateError::UnexpectedEnd);
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    let preset_dictionary = flg & 0x20 != 0;
+    if cmf & 0x0f != 8
+        || cmf >> 4 > 7
+        || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31)
+        || preset_dictionary
+    {
+        return Err(InflateError::InvalidHeader);
+    }
+
+    let mut input = BitReader::new(&data[2..]);
+    let out = inflate(&mut input)?;
+    input.align();
+    let checksum = input.bytes(4)?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err(InflateError::ChecksumMismatch);
+    }
+    Ok(out)
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bytes: vec![],
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// Append the `count` lowest bits of `bits`, least significant first
+    fn bits(&mut self, bits: u32, count: u32) {
+        self.bit_buffer |= bits << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// Append a Huffman code, which is stored most significant bit first
+    fn code(&mut self, code: u32, length: u32) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn align(&mut self) {
+        if self.bit_count > 0 {
+            self.bits(0, 8 - self.bit_count);
+        }
+    }
+}
+
+fn write_stored(out: &mut BitWriter, data: &[u8]) {
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        // an empty stream still needs a final block
+        out.bits(1, 3);
+        out.align();
+        out.bytes.extend_from_slice(&[0, 0, 0xff, 0xff]);
+        return;
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.bits(last as u32, 3);
+        out.align();
+        let len = block.len() as u16;
+        out.bytes.extend_from_slice(&len.to_le_bytes());
+        out.bytes.extend_from_slice(&(!len).to_le_bytes());
+        out.bytes.extend_from_slice(block);
+    }
+}
+
+/// Write `symbol` of the literal/length alphabet with the fixed codes
+fn write_fixed_symbol(out: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => out.code(0x30 + symbol, 8),
+        144..=255 => out.code(0x190 + symbol - 144, 9),
+        256..=279 => out.code(symbol - 256, 7),
+        _ => out.code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let i = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_fixed_symbol(out, 257 + i as u32);
+    out.bits(
+        (length - LENGTH_BASE[i] as usize) as u32,
+        LENGTH_EXTRA[i] as u32,
+    );
+
+    let i = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    out.code(i as u32, 5);
+    out.bits(
+        (distance - DISTANCE_BASE[i] as usize) as u32,
+        DISTANCE_EXTRA[i] as u32,
+    );
+}
+
+/// Earlier positions of the data, chained by the hash of the three bytes
+/// starting at them
+struct HashChains {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl HashChains {
+    const NONE: usize = usize::MAX;
+
+    fn new() -> HashChains {
+        HashChains {
+            head: vec![HashChains::NONE; 1 << 15],
+            prev: vec![HashChains::NONE; WINDOW],
+        }
+    }
+
+    fn hash(data: &[u8], i: usize) -> usize {
+        ((data[i] as usize) << 10 ^ (data[i + 1] as usize) << 5 ^ data[i + 2] as usize) & 0x7fff
+    }
+
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + MIN_MATCH <= data.len() {
+            let h = HashChains::hash(data, i);
+            self.prev[i % WINDOW] = self.head[h];
+            self.head[h] = i;
+        }
+    }
+
+    /// Length and distance of the longest earlier match for position `i`
+    fn longest_match(&self, data: &[u8], i: usize) -> (usize, usize) {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if i + MIN_MATCH > data.len() {
+            return (best_length, best_distance);
+        }
+
+        let max = MAX_MATCH.min(data.len() - i);
+        let mut candidate = self.head[HashChains::hash(data, i)];
+        for _ in 0..MAX_CHAIN {
+            if candidate == HashChains::NONE || i - candidate > WINDOW {
+                break;
+            }
+            let length = data[candidate..]
+                .iter()
+                .zip(&data[i..i + max])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best_length {
+                best_length = length;
+                best_distance = i - candidate;
+                if length == max {
+                    break;
+                }
+            }
+            let next = self.prev[candidate % WINDOW];
+            // older positions may have been overwritten by newer ones
+            if next >= candidate {
+                break;
+            }
+            candidate = next;
+        }
+        (best_length, best_distance)
+    }
+}
+
+/// Compress `data` into a single block with fixed Huffman codes
+fn write_fixed(out: &mut BitWriter, data: &[u8]) {
+    let mut chains = HashChains::new();
+    out.bits(1, 1);
+    out.bits(1, 2);
+
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = chains.longest_match(data, i);
+        if length >= MIN_MATCH {
+            write_match(out, length, distance);
+            for j in i..i + length {
+                chains.insert(data, j);
+            }
+            i += length;
+        } else {
+            write_fixed_symbol(out, data[i] as u32);
+            chains.insert(data, i);
+            i += 1;
+        }
+    }
+    write_fixed_symbol(out, 256);
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            pos: 0,
+            bit: 0,
+        }
+    }
+
+    fn bit(&mut self) -> Result<u32, InflateError> {
+        let byte = self.data.get(self.pos).ok_or(InflateError::UnexpectedEnd)?;
+        let b = (*byte as u32 >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(b)
+    }
+
+    /// Read `count` bits, least significant first
+    fn bits(&mut self, count: u32) -> Result<u32, InflateError> {
+        let mut v = 0;
+        for i in 0..count {
+            v |= self.bit()? << i;
+        }
+        Ok(v)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+
+    /// Read `count` whole bytes, must be aligned
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], InflateError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + count)
+            .ok_or(InflateError::UnexpectedEnd)?;
+        self.pos += count;
+        Ok(bytes)
+    }
+}
+
+/// Canonical Huffman code given by the number of codes of every length
+/// and the symbols ordered by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0; 16];
+        for l in 1..16 {
+            offsets[l] = offsets[l - 1] + counts[l - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = symbol as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn fixed_literals() -> Huffman {
+        let mut lengths = [8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Huffman::new(&lengths)
+    }
+
+    fn fixed_distances() -> Huffman {
+        Huffman::new(&[5; 30])
+    }
+
+    fn decode(&self, input: &mut BitReader) -> Result<u16, InflateError> {
+        // codes of every length are consecutive numbers starting at `first`
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= input.bit()? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(InflateError::InvalidCode)
+    }
+}
+
+fn inflate(input: &mut BitReader) -> Result<Vec<u8>, InflateError> {
+    let mut out = vec![];
+    loop {
+        let last = input.bit()? == 1;
+        match input.bits(2)? {
+            0 => {
+                input.align();
+                let header = input.bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(InflateError::InvalidStoredLength);
+                }
+                out.extend_from_slice(input.bytes(len as usize)?);
+            }
+            1 => {
+                let (literals, distances) = (Huffman::fixed_literals(), Huffman::fixed_distances());
+                inflate_block(input, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(input)?;
+                inflate_block(input, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(InflateError::InvalidBlockType),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn read_dynamic_codes(input: &mut BitReader) -> Result<(Huffman, Huffman), InflateError> {
+    let literal_count = input.bits(5)? as usize + 257;
+    let distance_count = input.bits(5)? as usize + 1;
+    let code_length_count = input.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[i] = input.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(input)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or(InflateError::InvalidCode)?;
+                (previous, 3 + input.bits(2)?)
+            }
+            17 => (0, 3 + input.bits(3)?),
+            _ => (0, 11 + input.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(InflateError::InvalidCode);
+    }
+
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn inflate_block(
+    input: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), InflateError> {
+    loop {
+        let symbol = literals.decode(input)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                let length = LENGTH_BASE[i] as usize + input.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let i = distances.decode(input)? as usize;
+                if i >= DISTANCE_BASE.len() {
+                    return Err(InflateError::InvalidCode);
+                }
+                let distance =
+                    DISTANCE_BASE[i] as usize + input.bits(DISTANCE_EXTRA[i] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(InflateError::InvalidDistance);
+                }
+                // the copy may overlap the bytes it produces
+                let start = out.len() - distance;
+                for j in 0..length {
+                    out.push(out[start + j]);
+                }
+            }
+            _ => return Err(InflateError::InvalidCode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes that are partly random and partly repetitive
+    fn sample_data(len: usize) -> Vec<u8> {
+        let mut seed = 12345u32;
+        (0..len)
+            .map(|i| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                if (i / 1000) % 2 == 0 {
+                    (seed >> 16) as u8
+                } else {
+                    (i % 7) as u8
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn computing_adler32() {
+        assert_eq!(1, adler32(b""));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn stored_stream_round_trips() {
+        for &len in [0, 1, 65535, 65536, 200_000].iter() {
+            let data = sample_data(len);
+            let compressed = zlib_compress(&data, Compression::Stored);
+
+            assert_eq!(data, zlib_decompress(&compressed).unwrap());
+        }
+    }
+
+    #[test]
+    fn compressed_stream_round_trips() {
+        for &len in [0, 1, 2, 3, 300, 100_000].iter() {
+            let data = sample_data(len);
+            let compressed = zlib_compress(&data, Compression::Compressed);
+
+            assert_eq!(data, zlib_decompress(&compressed).unwrap());
+        }
+    }
+
+    #[test]
+    fn stored_stream_has_length_headers() {
+        assert_eq!(
+            vec![
+                0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o', 0x06, 0x2c,
+                0x02, 0x15
+            ],
+            zlib_compress(b"hello", Compression::Stored)
+        );
+        assert_eq!(
+            vec![0x78, 0x01, 0x01, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01],
+            zlib_compress(b"", Compression::Stored)
+        );
+    }
+
+    #[test]
+    fn compressed_stream_uses_matches() {
+        assert_eq!(
+            vec![0x78, 0x9c, 0x4b, 0x4c, 0x4a, 0x86, 0x23, 0x00, 0x1d, 0xe0, 0x04, 0x99],
+            zlib_compress(b"abcabcabcabc", Compression::Compressed)
+        );
+    }
+
+    #[test]
+    fn repetitive_data_is_compressed() {
+        let data = vec![7; 100_000];
+        let compressed = zlib_compress(&data, Compression::Compressed);
+
+        assert!(compressed.len() < 1000);
+        assert_eq!(data, zlib_decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn decompressing_stream_with_fixed_codes() {
+        // zlib.compress(b"hello hello hello")
+        let compressed = [
+            0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00, 0x3a, 0x2e,
+            0x06, 0x7d,
+        ];
+
+        assert_eq!(
+            b"hello hello hello".to_vec(),
+            zlib_decompress(&compressed).unwrap()
+        );
+    }
+
+    #[test]
+    fn decompressing_stream_with_dynamic_codes() {
+        let letters = b"eeeeeeeettttaaoinshr";
+        let expected: Vec<u8> = (0..1800)
+            .map(|i| letters[(i * 7919 + i * i * 31) % 20])
+            .collect();
+        // zlib.compress(expected, 9)
+        let compressed = [
+            0x78, 0xda, 0xed, 0xc8, 0xb1, 0x0d, 0x00, 0x00, 0x08, 0xc3, 0xb0, 0x5b, 0x3b, 0x64,
+            0x65, 0xea, 0xff, 0x42, 0xe2, 0x0d, 0xe2, 0xd1, 0x94, 0x49, 0x9b, 0xa1, 0x84, 0x13,
+            0x3c, 0xcf, 0xf3, 0x3c, 0xef, 0xc9, 0x2d, 0xc5, 0x03, 0xdc, 0x13,
+        ];
+
+        assert_eq!(expected, zlib_decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn corrupted_streams_are_rejected() {
+        let mut compressed = zlib_compress(b"some data", Compression::Compressed);
+
+        assert_eq!(
+            Err(InflateError::InvalidHeader),
+            zlib_decompress(&[0x78, 0x00])
+        );
+        assert_eq!(
+            Err(InflateError::UnexpectedEnd),
+            zlib_decompress(&compressed[..compressed.len() - 5])
+        );
+        let last = compressed.len() - 1;
+        compressed[last] ^= 1;
+        assert_eq!(
+            Err(InflateError::ChecksumMismatch),
+            zlib_decompress(&compressed)
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+
+/// Default number of children at which `World::build_bvh` and OBJ meshes
+/// split a group
+pub const DIVIDE_THRESHOLD: usize = 4;
+
+type Children = Vec<Box<dyn Shape>>;
+
+/// Collection of shapes transformed together.
+///
+/// The group's transform is applied on top of each child's own transform.
+/// Children keep a link to the transforms of every enclosing group so that
+/// normals can be converted back to world space, so the transform is only
+/// changed through `set_transform`.
+///
+/// The bounds of the children are kept up to date as they are added, so rays
+/// that miss the group's box skip every child. `divide` splits large groups
+/// into a bounding volume hierarchy.
+#[derive(Debug)]
+pub struct Group {
+    transform: Transform,
+    pub material: Material,
+    children: Children,
+    parent: Option<Arc<Parent>>,
+    bounds: BoundingBox,
+}
+
+impl Group {
+    pub fn new() -> Group {
+        Group {
+            transform: Transform::identity(),
+            material: Material::new(),
+            children: vec![],
+            parent: None,
+            bounds: BoundingBox::empty(),
+        }
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn add_child<S: Shape>(&mut self, child: S) {
+        self.add_boxed_child(Box::new(child));
+    }
+
+    pub fn add_boxed_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent(Some(self.node()));
+        self.bounds.merge(&child.parent_space_bounds());
+        self.children.push(child);
+    }
+
+    /// Move the children that fit entirely in either half of the group's
+    /// bounds into two new subgroups. Children that straddle the split, or
+    /// are unbounded, stay where they are.
+    fn partition_children(&mut self) -> (Children, Children) {
+        let mut finite = BoundingBox::empty();
+        for child in &self.children {
+            let b = child.parent_space_bounds();
+            if b.is_finite() {
+                finite.merge(&b);
+            }
+        }
+        if finite.is_empty() {
+            return (vec![], vec![]);
+        }
+
+        let (left_box, right_box) = finite.split();
+        let (mut left, mut right, mut rest) = (vec![], vec![], vec![]);
+        for child in self.children.drain(..) {
+            let b = child.parent_space_bounds();
+            if left_box.contains_box(&b) {
+                left.push(child);
+            } else if right_box.contains_box(&b) {
+                right.push(child);
+            } else {
+                rest.push(child);
+            }
+        }
+        self.children = rest;
+        (left, right)
+    }
+
+    fn make_subgroup(&mut self, children: Children) {
+        let mut subgroup = Group::new();
+        for child in children {
+            subgroup.add_boxed_child(child);
+        }
+        self.add_child(subgroup);
+    }
+
+    /// Parent link handed to the children of this group
+    fn node(&self) -> Arc<Parent> {
+        Arc::new(Parent {
+            transform: self.transform.clone(),
+            parent: self.parent.clone(),
+        })
+    }
+
+    fn update_children(&mut self) {
+        let node = self.node();
+        for child in &mut self.children {
+            child.set_parent(Some(node.clone()));
+        }
+    }
+}
+
+impl PartialEq for Group {
+    // the bounds follow from the children, and may be infinite
+    fn eq(&self, other: &Group) -> bool {
+        self.transform == other.transform
+            && self.material == other.material
+            && self.children == other.children
+            && self.parent == other.parent
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Group {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+        self.update_children();
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+        self.update_children();
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds.intersects(ray) {
+            return vec![];
+        }
+
+        let xs: Vec<_> = self
+            .children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .collect();
+        intersection::intersections(&xs)
+    }
+
+    /// # Panics
+    ///
+    /// Always, since a group has no surface of its own. Intersections always
+    /// refer to the child that was hit, so its normal is used instead.
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("groups have no surface; normals come from their children")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.children.iter().any(|child| child.includes(other))
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        if threshold <= self.children.len() {
+            let count = self.children.len();
+            let (left, right) = self.partition_children();
+            if left.len() == count || right.len() == count {
+                // nothing would be gained, put the children back
+                for child in left.into_iter().chain(right) {
+                    self.children.push(child);
+                }
+            } else {
+                if !left.is_empty() {
+                    self.make_subgroup(left);
+                }
+                if !right.is_empty() {
+                    self.make_subgroup(right);
+                }
+            }
+        }
+
+        for child in &mut self.children {
+            child.divide(threshold);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cylinder::Cylinder;
+    use crate::sphere::Sphere;
+    use crate::transformation;
+
+    use std::f64::consts::PI;
+
+    #[test]
+    fn creating_new_group() {
+        let g = Group::new();
+
+        assert_eq!(Matrix4::identity(), *g.transform());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn adding_child_to_group() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+
+        assert_eq!(1, g.children().len());
+        assert!(g.children()[0].parent().is_some());
+    }
+
+    #[test]
+    fn intersecting_ray_with_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_ray_with_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.set_transform(transformation::translation(0.0, 0.0, -3.0));
+        let mut s3 = Sphere::new();
+        s3.set_transform(transformation::translation(5.0, 0.0, 0.0));
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.local_intersect(&r);
+
+        assert_eq!(4, xs.len());
+        let first = g.children()[1].as_ref();
+        let second = g.children()[0].as_ref();
+        assert!(xs[0].object == first);
+        assert!(xs[1].object == first);
+        assert!(xs[2].object == second);
+        assert!(xs[3].object == second);
+    }
+
+    #[test]
+    fn intersecting_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transform(transformation::translation(5.0, 0.0, 0.0));
+        g.add_child(s);
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(2, g.intersect(&r).len());
+    }
+
+    /// Builds the nested groups used by the book: a sphere translated by
+    /// (5, 0, 0) inside a group scaled by 2 inside a group rotated around y.
+    fn nested_sphere() -> Group {
+        let mut g2 = Group::new();
+        g2.set_transform(transformation::scaling(1.0, 2.0, 3.0));
+        let mut s = Sphere::new();
+        s.set_transform(transformation::translation(5.0, 0.0, 0.0));
+        g2.add_child(s);
+
+        let mut g1 = Group::new();
+        g1.set_transform(transformation::rotation_y(PI / 2.0));
+        g1.add_child(g2);
+        g1
+    }
+
+    fn inner_sphere(g1: &Group) -> &dyn Shape {
+        let g2 = g1.children()[0].as_any().downcast_ref::<Group>().unwrap();
+        g2.children()[0].as_ref()
+    }
+
+    #[test]
+    fn converting_point_from_world_to_object_space() {
+        let mut g1 = Group::new();
+        g1.set_transform(transformation::rotation_y(PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transform(transformation::translation(5.0, 0.0, 0.0));
+        g2.add_child(s);
+        g1.add_child(g2);
+
+        let s = inner_sphere(&g1);
+        let p = s.world_to_object(Point::new(-2.0, 0.0, -10.0));
+
+        assert_eq!(Point::new(0.0, 0.0, -1.0), p);
+    }
+
+    #[test]
+    fn converting_normal_from_object_to_world_space() {
+        let g1 = nested_sphere();
+        let s = inner_sphere(&g1);
+        let v = 3f64.sqrt() / 3.0;
+        let n = s.normal_to_world(Vector::new(v, v, v));
+
+        assert_eq!(Vector::new(2.0 / 7.0, 3.0 / 7.0, -6.0 / 7.0), n);
+    }
+
+    #[test]
+    fn finding_normal_on_child_object() {
+        let g1 = nested_sphere();
+        let s = inner_sphere(&g1);
+        let n = s.normal_at(Point::new(1.7321, 1.1547, -5.5774));
+
+        assert_eq!(Vector::new(0.2857, 0.42854, -0.85716), n);
+    }
+
+    #[test]
+    #[should_panic(expected = "groups have no surface")]
+    fn group_has_no_normal_of_its_own() {
+        nested_sphere().normal_at(Point::new(1.7321, 1.1547, -5.5774));
+    }
+
+    #[test]
+    fn changing_group_transform_updates_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.set_transform(transformation::translation(0.0, 0.0, 10.0));
+
+        let p = g.children()[0].world_to_object(Point::new(0.0, 0.0, 10.0));
+        assert_eq!(Point::new(0.0, 0.0, 0.0), p);
+    }
+
+    #[test]
+    fn group_has_bounding_box_that_contains_its_children() {
+        let mut s = Sphere::new();
+        s.set_transform(
+            transformation::translation(2.0, 5.0, -3.0) * transformation::scaling(2.0, 2.0, 2.0),
+        );
+        let mut c = Cylinder::new();
+        c.minimum = -2.0;
+        c.maximum = 2.0;
+        c.set_transform(
+            transformation::translation(-4.0, -1.0, 4.0) * transformation::scaling(0.5, 1.0, 0.5),
+        );
+        let mut g = Group::new();
+        g.add_child(s);
+        g.add_child(c);
+        let b = g.bounds();
+
+        assert_eq!(Point::new(-4.5, -3.0, -5.0), b.min);
+        assert_eq!(Point::new(4.0, 7.0, 4.5), b.max);
+    }
+
+    #[test]
+    fn ray_missing_group_bounds_skips_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(!g.bounds().intersects(&r));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    fn sphere_at(x: f64, y: f64, z: f64) -> Sphere {
+        let mut s = Sphere::new();
+        s.set_transform(transformation::translation(x, y, z));
+        s
+    }
+
+    fn child_group(g: &Group, index: usize) -> &Group {
+        g.children()[index]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap()
+    }
+
+    #[test]
+    fn partitioning_group_children() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(-2.0, 0.0, 0.0));
+        g.add_child(sphere_at(2.0, 0.0, 0.0));
+        g.add_child(Sphere::new());
+        let (left, right) = g.partition_children();
+
+        assert_eq!(1, g.children().len());
+        assert_eq!(Matrix4::identity(), *g.children()[0].transform());
+        assert_eq!(1, left.len());
+        assert_eq!(*sphere_at(-2.0, 0.0, 0.0).transform(), *left[0].transform());
+        assert_eq!(1, right.len());
+        assert_eq!(*sphere_at(2.0, 0.0, 0.0).transform(), *right[0].transform());
+    }
+
+    #[test]
+    fn subdividing_group_partitions_its_children() {
+        let mut g = Group::new();
+        g.add_child(sphere_at(-2.0, -2.0, 0.0));
+        g.add_child(sphere_at(-2.0, 2.0, 0.0));
+        let mut s3 = Sphere::new();
+        s3.set_transform(transformation::scaling(4.0, 4.0, 4.0));
+        g.add_child(s3);
+        g.divide(1);
+
+        assert_eq!(2, g.children().len());
+        let sub = child_group(&g, 1);
+        assert_eq!(2, sub.children().len());
+        assert_eq!(1, child_group(sub, 0).children().len());
+        assert_eq!(1, child_group(sub, 1).children().len());
+    }
+
+    #[test]
+    fn subdividing_group_with_too_few_children() {
+        let mut subgroup = Group::new();
+        subgroup.add_child(sphere_at(-2.0, 0.0, 0.0));
+        subgroup.add_child(sphere_at(2.0, 1.0, 0.0));
+        subgroup.add_child(sphere_at(2.0, -1.0, 0.0));
+        let mut s4 = Sphere::new();
+        s4.set_transform(transformation::scaling(4.0, 4.0, 4.0));
+        let mut g = Group::new();
+        g.add_child(subgroup);
+        g.add_child(s4);
+        g.divide(3);
+
+        assert_eq!(2, g.children().len());
+        let subgroup = child_group(&g, 0);
+        assert_eq!(2, subgroup.children().len());
+        assert_eq!(1, child_group(subgroup, 0).children().len());
+        assert_eq!(2, child_group(subgroup, 1).children().len());
+    }
+
+    #[test]
+    fn dividing_identical_children_terminates() {
+        let mut g = Group::new();
+        for _ in 0..10 {
+            g.add_child(sphere_at(1.0, 1.0, 1.0));
+        }
+        g.divide(2);
+
+        let r = Ray::new(Point::new(1.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(20, g.intersect(&r).len());
+    }
+
+    #[test]
+    fn divided_group_finds_same_intersections() {
+        let mut g = Group::new();
+        for i in 0..50 {
+            g.add_child(sphere_at(i as f64 * 2.5, (i % 3) as f64, 0.0));
+        }
+        let r = Ray::new(Point::new(-5.0, 0.2, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let before: Vec<f64> = g.intersect(&r).iter().map(|x| x.t).collect();
+        g.divide(4);
+        let after: Vec<f64> = g.intersect(&r).iter().map(|x| x.t).collect();
+
+        assert_eq!(before, after);
+        assert!(g.children().len() < 50);
+    }
+
+    #[test]
+    fn group_includes_its_descendants() {
+        let mut inner = Group::new();
+        inner.add_child(Sphere::new());
+        let mut g = Group::new();
+        g.add_child(inner);
+        let other = Sphere::new();
+
+        let sphere = child_group(&g, 0).children()[0].as_ref();
+        assert!(g.includes(sphere));
+        assert!(!g.includes(&other));
+    }
+}
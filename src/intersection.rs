@@ -1,46 +1,108 @@
 use std::cmp::Ordering;
+use std::ptr;
 
+use crate::point::Point;
 use crate::ray::Ray;
-use crate::sphere::Sphere;
-use crate::tuple::Tuple;
+use crate::shape::Shape;
+use crate::vector::Vector;
+use crate::EPSILON;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Intersection<'a> {
     pub t: f64,
-    pub object: &'a Sphere,
+    pub object: &'a dyn Shape,
+    /// Barycentric coordinates of the hit on triangles
+    pub uv: Option<(f64, f64)>,
 }
 
 pub struct Computations<'a> {
     pub t: f64,
-    pub object: &'a Sphere,
-    pub point: Tuple,
-    pub eyev: Tuple,
-    pub normalv: Tuple,
+    pub object: &'a dyn Shape,
+    pub point: Point,
+    pub over_point: Point,
+    pub under_point: Point,
+    pub eyev: Vector,
+    pub normalv: Vector,
+    pub reflectv: Vector,
     pub inside: bool,
+    pub n1: f64,
+    pub n2: f64,
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(t: f64, object: &'a Sphere) -> Intersection<'a> {
-        Intersection { t, object }
+    pub fn new(t: f64, object: &'a dyn Shape) -> Intersection<'a> {
+        Intersection {
+            t,
+            object,
+            uv: None,
+        }
+    }
+
+    pub fn with_uv(t: f64, object: &'a dyn Shape, u: f64, v: f64) -> Intersection<'a> {
+        Intersection {
+            t,
+            object,
+            uv: Some((u, v)),
+        }
     }
 
-    pub fn prepare_computations(&self, r: &Ray) -> Computations {
+    /// Precompute the state of the hit for shading. `xs` is the sorted list
+    /// of all intersections along `r`, used to find the refractive indices
+    /// on both sides of the hit.
+    pub fn prepare_computations(&self, r: &Ray, xs: &[Intersection<'a>]) -> Computations<'a> {
         let eyev_ = -r.direction;
-        let mut normalv_ = self.object.normal_at(r.position(self.t));
+        let mut normalv_ = self.object.normal_at_hit(r.position(self.t), self);
         let mut inside_ = false;
         if normalv_.dot(eyev_) < 0.0 {
             inside_ = true;
             normalv_ = -normalv_;
         }
 
+        let point_ = r.position(self.t);
+        let (n1_, n2_) = self.refractive_indices(xs);
+
         Computations {
             t: self.t,
             object: self.object,
-            point: r.position(self.t),
+            point: point_,
+            over_point: point_ + normalv_ * EPSILON,
+            under_point: point_ - normalv_ * EPSILON,
             eyev: eyev_,
             normalv: normalv_,
+            reflectv: r.direction.reflect(normalv_),
             inside: inside_,
+            n1: n1_,
+            n2: n2_,
+        }
+    }
+
+    fn refractive_indices(&self, xs: &[Intersection<'a>]) -> (f64, f64) {
+        fn last_index(containers: &[&dyn Shape]) -> f64 {
+            containers
+                .last()
+                .map_or(1.0, |o| o.material().refractive_index)
         }
+
+        let mut containers: Vec<&dyn Shape> = vec![];
+        let mut n1 = 1.0;
+        for x in xs {
+            let is_hit = x.t == self.t && ptr::addr_eq(x.object, self.object);
+            if is_hit {
+                n1 = last_index(&containers);
+            }
+
+            match containers.iter().position(|&o| ptr::addr_eq(o, x.object)) {
+                Some(i) => {
+                    containers.remove(i);
+                }
+                None => containers.push(x.object),
+            }
+
+            if is_hit {
+                return (n1, last_index(&containers));
+            }
+        }
+        (n1, 1.0)
     }
 }
 
@@ -51,20 +113,36 @@ pub fn intersections<'a>(xs: &[Intersection<'a>]) -> Vec<Intersection<'a>> {
 }
 
 pub fn hit<'a>(xs: &'a [Intersection]) -> Option<&'a Intersection<'a>> {
-    for x in xs {
-        if x.t >= 0.0 {
-            return Some(x);
+    xs.iter().find(|x| x.t >= 0.0)
+}
+
+/// Schlick approximation of the Fresnel reflectance at the hit
+pub fn schlick(comps: &Computations) -> f64 {
+    let mut cos = comps.eyev.dot(comps.normalv);
+
+    if comps.n1 > comps.n2 {
+        let n = comps.n1 / comps.n2;
+        let sin2_t = n * n * (1.0 - cos * cos);
+        if sin2_t > 1.0 {
+            // total internal reflection
+            return 1.0;
         }
+        cos = (1.0 - sin2_t).sqrt();
     }
-    None
+
+    let r0 = ((comps.n1 - comps.n2) / (comps.n1 + comps.n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::plane::Plane;
     use crate::ray::Ray;
+    use crate::shape::Shape;
     use crate::sphere::Sphere;
-    use crate::tuple::Tuple;
+    use crate::transformation;
+    use crate::triangle::SmoothTriangle;
 
     #[test]
     fn intersection_encapsulates_t_and_object() {
@@ -72,7 +150,7 @@ mod tests {
         let i = Intersection::new(3.5, &s);
 
         assert_eq!(3.5, i.t);
-        assert_eq!(&s, i.object);
+        assert_eq!(&s as &dyn Shape, i.object);
     }
 
     #[test]
@@ -80,7 +158,7 @@ mod tests {
         let s = Sphere::new();
         let i1 = Intersection::new(1.0, &s);
         let i2 = Intersection::new(2.0, &s);
-        let xs = intersections(&vec![i1, i2]);
+        let xs = intersections(&[i1, i2]);
 
         assert_eq!(2, xs.len());
         assert_eq!(1.0, xs[0].t);
@@ -92,7 +170,7 @@ mod tests {
         let s = Sphere::new();
         let i1 = Intersection::new(1.0, &s);
         let i2 = Intersection::new(2.0, &s);
-        let xs = intersections(&vec![i1, i2]);
+        let xs = intersections(&[i1, i2]);
         let i = hit(&xs);
 
         assert_eq!(Some(&i1), i);
@@ -103,7 +181,7 @@ mod tests {
         let s = Sphere::new();
         let i1 = Intersection::new(-1.0, &s);
         let i2 = Intersection::new(1.0, &s);
-        let xs = intersections(&vec![i1, i2]);
+        let xs = intersections(&[i1, i2]);
         let i = hit(&xs);
 
         assert_eq!(Some(&i2), i);
@@ -114,7 +192,7 @@ mod tests {
         let s = Sphere::new();
         let i1 = Intersection::new(-2.0, &s);
         let i2 = Intersection::new(-1.0, &s);
-        let xs = intersections(&vec![i1, i2]);
+        let xs = intersections(&[i1, i2]);
         let i = hit(&xs);
 
         assert_eq!(None, i);
@@ -127,7 +205,7 @@ mod tests {
         let i2 = Intersection::new(7.0, &s);
         let i3 = Intersection::new(-3.0, &s);
         let i4 = Intersection::new(2.0, &s);
-        let xs = intersections(&vec![i1, i2, i3, i4]);
+        let xs = intersections(&[i1, i2, i3, i4]);
         let i = hit(&xs);
 
         assert_eq!(Some(&i4), i);
@@ -135,40 +213,182 @@ mod tests {
 
     #[test]
     fn precomputing_state_of_intersection() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::new();
         let i = Intersection::new(4.0, &shape);
 
-        let comps = i.prepare_computations(&r);
+        let comps = i.prepare_computations(&r, &[i]);
         assert_eq!(i.t, comps.t);
         assert_eq!(i.object, comps.object);
-        assert_eq!(Tuple::point(0.0, 0.0, -1.0), comps.point);
-        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), comps.eyev);
-        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), comps.normalv);
+        assert_eq!(Point::new(0.0, 0.0, -1.0), comps.point);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.eyev);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
     }
 
     #[test]
     fn hit_when_intersection_occurs_on_outside() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::new();
         let i = Intersection::new(4.0, &shape);
 
-        let comps = i.prepare_computations(&r);
-        assert_eq!(false, comps.inside);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(!comps.inside);
     }
 
     #[test]
     fn hit_when_intersection_occurs_on_inside() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::new();
         let i = Intersection::new(1.0, &shape);
 
-        let comps = i.prepare_computations(&r);
+        let comps = i.prepare_computations(&r, &[i]);
         assert_eq!(i.t, comps.t);
         assert_eq!(i.object, comps.object);
-        assert_eq!(Tuple::point(0.0, 0.0, 1.0), comps.point);
-        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), comps.eyev);
-        assert_eq!(true, comps.inside);
-        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), comps.normalv);
+        assert_eq!(Point::new(0.0, 0.0, 1.0), comps.point);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.eyev);
+        assert!(comps.inside);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
+    }
+
+    #[test]
+    fn hit_should_offset_point() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut shape = Sphere::new();
+        shape.set_transform(transformation::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.over_point.2 < -EPSILON / 2.0);
+        assert!(comps.point.2 > comps.over_point.2);
+    }
+
+    #[test]
+    fn precomputing_reflection_vector() {
+        let shape = Plane::new();
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let i = Intersection::new(f64::sqrt(2.0), &shape);
+
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(
+            Vector::new(0.0, f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+            comps.reflectv
+        );
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let mut a = Sphere::glass();
+        a.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        a.material.refractive_index = 1.5;
+        let mut b = Sphere::glass();
+        b.set_transform(transformation::translation(0.0, 0.0, -0.25));
+        b.material.refractive_index = 2.0;
+        let mut c = Sphere::glass();
+        c.set_transform(transformation::translation(0.0, 0.0, 0.25));
+        c.material.refractive_index = 2.5;
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = intersections(&[
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, &(n1, n2)) in expected.iter().enumerate() {
+            let comps = xs[i].prepare_computations(&r, &xs);
+            assert_eq!(n1, comps.n1);
+            assert_eq!(n2, comps.n2);
+        }
+    }
+
+    #[test]
+    fn under_point_is_offset_below_surface() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut shape = Sphere::glass();
+        shape.set_transform(transformation::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let xs = intersections(&[i]);
+
+        let comps = i.prepare_computations(&r, &xs);
+        assert!(comps.under_point.2 > EPSILON / 2.0);
+        assert!(comps.point.2 < comps.under_point.2);
+    }
+
+    #[test]
+    fn schlick_approximation_under_total_internal_reflection() {
+        let shape = Sphere::glass();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, f64::sqrt(2.0) / 2.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = intersections(&[
+            Intersection::new(-f64::sqrt(2.0) / 2.0, &shape),
+            Intersection::new(f64::sqrt(2.0) / 2.0, &shape),
+        ]);
+
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(1.0, schlick(&comps));
+    }
+
+    #[test]
+    fn schlick_approximation_with_perpendicular_viewing_angle() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = intersections(&[
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ]);
+
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert!((schlick(&comps) - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = intersections(&[Intersection::new(1.8589, &shape)]);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert!((schlick(&comps) - 0.48873).abs() < EPSILON);
+    }
+
+    #[test]
+    fn intersection_can_encapsulate_u_and_v() {
+        let s = Sphere::new();
+        let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+
+        assert_eq!(Some((0.2, 0.4)), i.uv);
+    }
+
+    #[test]
+    fn preparing_normal_on_smooth_triangle() {
+        let tri = SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        let i = Intersection::with_uv(1.0, &tri, 0.45, 0.25);
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = intersections(&[i]);
+
+        let comps = i.prepare_computations(&r, &xs);
+        assert_eq!(Vector::new(-0.5547, 0.83205, 0.0), comps.normalv);
     }
 }
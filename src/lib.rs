@@ -1,11 +1,31 @@
+pub mod bounds;
 pub mod camera;
 pub mod canvas;
+pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod deflate;
+pub mod group;
 pub mod intersection;
 pub mod lights;
 pub mod material;
 pub mod matrix;
+pub mod obj_file;
+pub mod pattern;
+pub mod plane;
+pub mod png;
+pub mod point;
+pub mod ppm;
 pub mod ray;
+pub mod scene;
+pub mod shape;
 pub mod sphere;
 pub mod transformation;
+pub mod triangle;
 pub mod tuple;
+pub mod vector;
 pub mod world;
+
+pub const EPSILON: f64 = 1e-5;
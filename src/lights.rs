@@ -1,13 +1,14 @@
-use crate::tuple::Tuple;
+use crate::color::Color;
+use crate::point::Point;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct PointLight {
-    pub intensity: Tuple,
-    pub position: Tuple,
+    pub intensity: Color,
+    pub position: Point,
 }
 
 impl PointLight {
-    pub fn new(pos: Tuple, inte: Tuple) -> PointLight {
+    pub fn new(pos: Point, inte: Color) -> PointLight {
         PointLight {
             position: pos,
             intensity: inte,
@@ -18,12 +19,11 @@ impl PointLight {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tuple::Tuple;
 
     #[test]
     fn point_light_has_position_and_intensity() {
-        let intensity = Tuple::color(1.0, 1.0, 1.0);
-        let position = Tuple::point(0.0, 0.0, 0.0);
+        let intensity = Color::new(1.0, 1.0, 1.0);
+        let position = Point::new(0.0, 0.0, 0.0);
         let light = PointLight::new(position, intensity);
 
         assert_eq!(position, light.position);
@@ -1,45 +1,73 @@
+use std::sync::Arc;
+
+use crate::color::Color;
 use crate::lights::PointLight;
-use crate::tuple;
-use crate::tuple::Tuple;
+use crate::pattern::Pattern;
+use crate::point::Point;
+use crate::shape::Shape;
+use crate::vector::Vector;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Material {
-    pub color: Tuple,
+    pub color: Color,
+    pub pattern: Option<Arc<dyn Pattern>>,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
 }
 
 impl Material {
     pub fn new() -> Material {
         Material {
-            color: Tuple::color(1.0, 1.0, 1.0),
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
         }
     }
 
-    pub fn lightning(self, light: PointLight, point: Tuple, eyev: Tuple, normalv: Tuple) -> Tuple {
-        let effective_color = self.color.hadamard(light.intensity);
+    pub fn lightning(
+        &self,
+        object: &dyn Shape,
+        light: PointLight,
+        point: Point,
+        eyev: Vector,
+        normalv: Vector,
+        in_shadow: bool,
+    ) -> Color {
+        let color = match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, point),
+            None => self.color,
+        };
+        let effective_color = color * light.intensity;
         let lightv = (light.position - point).normalize();
         let ambient = effective_color * self.ambient;
+        if in_shadow {
+            return ambient;
+        }
         let light_dot_normal = lightv.dot(normalv);
         let diffuse;
         let specular;
 
         if light_dot_normal < 0.0 {
-            diffuse = Tuple::color(0.0, 0.0, 0.0);
-            specular = Tuple::color(0.0, 0.0, 0.0);
+            diffuse = Color::new(0.0, 0.0, 0.0);
+            specular = Color::new(0.0, 0.0, 0.0);
         } else {
             diffuse = effective_color * self.diffuse * light_dot_normal;
-            let reflectv = tuple::reflect(-lightv, normalv);
+            let reflectv = (-lightv).reflect(normalv);
             let reflect_dot_eye = reflectv.dot(eyev);
 
             if reflect_dot_eye <= 0.0 {
-                specular = Tuple::color(0.0, 0.0, 0.0);
+                specular = Color::new(0.0, 0.0, 0.0);
             } else {
                 let factor = reflect_dot_eye.powf(self.shininess);
                 specular = light.intensity * self.specular * factor;
@@ -50,66 +78,123 @@ impl Material {
     }
 }
 
+impl Default for Material {
+    fn default() -> Material {
+        Material::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tuple::Tuple;
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
 
     #[test]
     fn default_material() {
         let m = Material::new();
-        assert_eq!(Tuple::color(1.0, 1.0, 1.0), m.color);
+        assert_eq!(Color::new(1.0, 1.0, 1.0), m.color);
         assert_eq!(0.1, m.ambient);
         assert_eq!(0.9, m.diffuse);
         assert_eq!(0.9, m.specular);
         assert_eq!(200.0, m.shininess);
+        assert_eq!(0.0, m.reflective);
+        assert_eq!(0.0, m.transparency);
+        assert_eq!(1.0, m.refractive_index);
     }
 
     #[test]
     fn lightning_with_eye_between_light_and_surface() {
         let m = Material::new();
-        let position = Tuple::point(0.0, 0.0, 0.0);
-        let eyev = Tuple::vector(0.0, 0.0, -1.0);
-        let normalv = Tuple::vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Tuple::color(1.0, 1.0, 1.0));
-        let result = m.lightning(light, position, eyev, normalv);
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lightning(&Sphere::new(), light, position, eyev, normalv, false);
 
-        assert_eq!(Tuple::color(1.9, 1.9, 1.9), result);
+        assert_eq!(Color::new(1.9, 1.9, 1.9), result);
     }
 
     #[test]
     fn lightning_with_eye_opposite_surface_light_offset_45() {
         let m = Material::new();
-        let position = Tuple::point(0.0, 0.0, 0.0);
-        let eyev = Tuple::vector(0.0, 0.0, -1.0);
-        let normalv = Tuple::vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Tuple::color(1.0, 1.0, 1.0));
-        let result = m.lightning(light, position, eyev, normalv);
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lightning(&Sphere::new(), light, position, eyev, normalv, false);
 
-        assert_eq!(Tuple::color(0.7364, 0.7364, 0.7364), result);
+        assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
     }
 
     #[test]
     fn lightning_with_eye_in_path_of_reflection_vector() {
         let m = Material::new();
-        let position = Tuple::point(0.0, 0.0, 0.0);
-        let eyev = Tuple::vector(0.0, -f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
-        let normalv = Tuple::vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Tuple::color(1.0, 1.0, 1.0));
-        let result = m.lightning(light, position, eyev, normalv);
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, -f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lightning(&Sphere::new(), light, position, eyev, normalv, false);
 
-        assert_eq!(Tuple::color(1.6364, 1.6364, 1.6364), result);
+        assert_eq!(Color::new(1.6364, 1.6364, 1.6364), result);
     }
 
     #[test]
     fn lightning_with_light_behind_surface() {
         let m = Material::new();
-        let position = Tuple::point(0.0, 0.0, 0.0);
-        let eyev = Tuple::vector(0.0, 0.0, -1.0);
-        let normalv = Tuple::vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Tuple::color(1.0, 1.0, 1.0));
-        let result = m.lightning(light, position, eyev, normalv);
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lightning(&Sphere::new(), light, position, eyev, normalv, false);
+
+        assert_eq!(Color::new(0.1, 0.1, 0.1), result);
+    }
 
-        assert_eq!(Tuple::color(0.1, 0.1, 0.1), result);
+    #[test]
+    fn lightning_with_surface_in_shadow() {
+        let m = Material::new();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lightning(&Sphere::new(), light, position, eyev, normalv, true);
+
+        assert_eq!(Color::new(0.1, 0.1, 0.1), result);
+    }
+
+    #[test]
+    fn lightning_with_pattern_applied() {
+        let mut m = Material::new();
+        m.pattern = Some(Arc::new(StripePattern::new(
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.0, 0.0, 0.0),
+        )));
+        m.ambient = 1.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
+        let object = Sphere::new();
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let c1 = m.lightning(
+            &object,
+            light,
+            Point::new(0.9, 0.0, 0.0),
+            eyev,
+            normalv,
+            false,
+        );
+        let c2 = m.lightning(
+            &object,
+            light,
+            Point::new(1.1, 0.0, 0.0),
+            eyev,
+            normalv,
+            false,
+        );
+
+        assert_eq!(Color::new(1.0, 1.0, 1.0), c1);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c2);
     }
 }
@@ -1,73 +1,145 @@
 use std::ops::Mul;
 
+use crate::point::Point;
 use crate::tuple::Tuple;
+use crate::vector::Vector;
 
 const EPS: f64 = 1e-5;
 
-/// Square Matrix
-#[derive(Debug)]
-pub struct Matrix {
-    pub dim: usize,
-    elems: Vec<f64>,
+/// Smallest determinant, relative to the product of the row lengths,
+/// that a matrix may have and still be inverted
+const SINGULAR_EPS: f64 = 1e-12;
+
+/// 2x2 matrix, only needed for determinants of larger matrices
+#[derive(Clone, Copy, Debug)]
+pub struct Matrix2 {
+    elems: [f64; 4],
 }
 
-impl Matrix {
-    pub fn from_vector(d: usize, e: &[f64]) -> Matrix {
-        Matrix {
-            dim: d,
-            elems: e.to_vec(),
-        }
-    }
+/// 3x3 matrix, only needed for determinants of 4x4 matrices
+#[derive(Clone, Copy, Debug)]
+pub struct Matrix3 {
+    elems: [f64; 9],
+}
 
-    pub fn identity() -> Matrix {
-        let v = [
-            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-        ];
-        Matrix::from_vector(4, &v)
+/// 4x4 matrix in row-major order
+#[derive(Clone, Copy, Debug)]
+pub struct Matrix4 {
+    elems: [f64; 16],
+}
+
+fn elems_eq(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < EPS)
+}
+
+impl Matrix2 {
+    pub fn new(elems: [f64; 4]) -> Matrix2 {
+        Matrix2 { elems }
     }
 
     pub fn at(&self, r: usize, c: usize) -> f64 {
-        self.elems[r * self.dim + c]
+        self.elems[r * 2 + c]
     }
 
-    pub fn tuple_prod(&self, t: Tuple) -> Tuple {
-        assert!(self.dim == 4);
-        let dot = |r: usize| -> f64 {
-            self.at(r, 0) * t.0 + self.at(r, 1) * t.1 + self.at(r, 2) * t.2 + self.at(r, 3) * t.3
-        };
-        Tuple(dot(0), dot(1), dot(2), dot(3))
+    pub fn det(&self) -> f64 {
+        self.at(0, 0) * self.at(1, 1) - self.at(0, 1) * self.at(1, 0)
     }
+}
 
-    pub fn transpose(&self) -> Matrix {
-        let mut v: Vec<f64> = Vec::with_capacity(self.dim * self.dim);
-        for r in 0..self.dim {
-            for c in 0..self.dim {
-                v.push(self.at(c, r));
+impl PartialEq for Matrix2 {
+    fn eq(&self, other: &Matrix2) -> bool {
+        elems_eq(&self.elems, &other.elems)
+    }
+}
+
+impl Matrix3 {
+    pub fn new(elems: [f64; 9]) -> Matrix3 {
+        Matrix3 { elems }
+    }
+
+    pub fn at(&self, r: usize, c: usize) -> f64 {
+        self.elems[r * 3 + c]
+    }
+
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix2 {
+        let mut elems = [0.0; 4];
+        let mut i = 0;
+        for r in (0..3).filter(|&r| r != row) {
+            for c in (0..3).filter(|&c| c != col) {
+                elems[i] = self.at(r, c);
+                i += 1;
             }
         }
-        Matrix::from_vector(self.dim, &v)
+        Matrix2::new(elems)
     }
 
-    pub fn det(&self) -> f64 {
-        if self.dim == 2 {
-            return self.at(0, 0) * self.at(1, 1) - self.at(0, 1) * self.at(1, 0);
+    pub fn minor(&self, row: usize, col: usize) -> f64 {
+        self.submatrix(row, col).det()
+    }
+
+    pub fn cofactor(&self, row: usize, col: usize) -> f64 {
+        if (row + col).is_multiple_of(2) {
+            self.minor(row, col)
         } else {
-            (0..self.dim)
-                .map(|c| self.at(0, c) * self.cofactor(0, c))
-                .sum()
+            -self.minor(row, col)
         }
     }
 
-    pub fn submatrix(&self, row: usize, col: usize) -> Matrix {
-        let mut v: Vec<f64> = Vec::with_capacity((self.dim - 1) * (self.dim - 1));
-        for r in 0..self.dim {
-            for c in 0..self.dim {
-                if r != row && c != col {
-                    v.push(self.at(r, c));
-                }
+    pub fn det(&self) -> f64 {
+        (0..3).map(|c| self.at(0, c) * self.cofactor(0, c)).sum()
+    }
+}
+
+impl PartialEq for Matrix3 {
+    fn eq(&self, other: &Matrix3) -> bool {
+        elems_eq(&self.elems, &other.elems)
+    }
+}
+
+impl Matrix4 {
+    pub fn new(elems: [f64; 16]) -> Matrix4 {
+        Matrix4 { elems }
+    }
+
+    pub fn identity() -> Matrix4 {
+        #[rustfmt::skip]
+        let elems = [
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        Matrix4::new(elems)
+    }
+
+    pub fn at(&self, r: usize, c: usize) -> f64 {
+        self.elems[r * 4 + c]
+    }
+
+    pub fn tuple_prod(&self, t: Tuple) -> Tuple {
+        self * t
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut elems = [0.0; 16];
+        for r in 0..4 {
+            for c in 0..4 {
+                elems[r * 4 + c] = self.at(c, r);
+            }
+        }
+        Matrix4::new(elems)
+    }
+
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix3 {
+        let mut elems = [0.0; 9];
+        let mut i = 0;
+        for r in (0..4).filter(|&r| r != row) {
+            for c in (0..4).filter(|&c| c != col) {
+                elems[i] = self.at(r, c);
+                i += 1;
             }
         }
-        Matrix::from_vector(self.dim - 1, &v)
+        Matrix3::new(elems)
     }
 
     pub fn minor(&self, row: usize, col: usize) -> f64 {
@@ -75,62 +147,184 @@ impl Matrix {
     }
 
     pub fn cofactor(&self, row: usize, col: usize) -> f64 {
-        if (row + col) % 2 == 0 {
+        if (row + col).is_multiple_of(2) {
             self.minor(row, col)
         } else {
-            -1.0 * self.minor(row, col)
+            -self.minor(row, col)
         }
     }
 
+    pub fn det(&self) -> f64 {
+        let (s, c) = self.pair_dets();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// True unless the determinant is zero or tiny compared to the size of
+    /// the rows, so that uniformly small scalings are still invertible
     pub fn invertible(&self) -> bool {
-        (self.det() - 0.0).abs() > EPS
+        Self::nonsingular(self.det(), self.row_norms())
     }
 
-    pub fn inverse(&self) -> Matrix {
-        assert!(self.invertible());
-        let det = self.det();
-        let mut v: Vec<f64> = Vec::with_capacity(self.dim);
-        for r in 0..self.dim {
-            for c in 0..self.dim {
-                v.push(self.cofactor(c, r) / det);
-            }
+    fn row_norms(&self) -> f64 {
+        self.elems
+            .chunks(4)
+            .map(|row| row.iter().map(|x| x * x).sum::<f64>().sqrt())
+            .product()
+    }
+
+    fn nonsingular(det: f64, row_norms: f64) -> bool {
+        // written so that NaN is singular
+        det != 0.0 && det.abs() > SINGULAR_EPS * row_norms
+    }
+
+    /// Closed-form inverse built from the 2x2 determinants of the top and
+    /// bottom two rows
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix is not `invertible`.
+    pub fn inverse(&self) -> Matrix4 {
+        self.try_inverse().expect("matrix is not invertible")
+    }
+
+    /// Inverse of the matrix, or `None` if it is not `invertible`
+    pub fn try_inverse(&self) -> Option<Matrix4> {
+        let (s, c) = self.pair_dets();
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if !Self::nonsingular(det, self.row_norms()) {
+            return None;
         }
-        Matrix::from_vector(self.dim, &v)
+
+        let a = |r: usize, col: usize| self.at(r, col);
+        #[rustfmt::skip]
+        let elems = [
+            a(1, 1) * c[5] - a(1, 2) * c[4] + a(1, 3) * c[3],
+            -a(0, 1) * c[5] + a(0, 2) * c[4] - a(0, 3) * c[3],
+            a(3, 1) * s[5] - a(3, 2) * s[4] + a(3, 3) * s[3],
+            -a(2, 1) * s[5] + a(2, 2) * s[4] - a(2, 3) * s[3],
+
+            -a(1, 0) * c[5] + a(1, 2) * c[2] - a(1, 3) * c[1],
+            a(0, 0) * c[5] - a(0, 2) * c[2] + a(0, 3) * c[1],
+            -a(3, 0) * s[5] + a(3, 2) * s[2] - a(3, 3) * s[1],
+            a(2, 0) * s[5] - a(2, 2) * s[2] + a(2, 3) * s[1],
+
+            a(1, 0) * c[4] - a(1, 1) * c[2] + a(1, 3) * c[0],
+            -a(0, 0) * c[4] + a(0, 1) * c[2] - a(0, 3) * c[0],
+            a(3, 0) * s[4] - a(3, 1) * s[2] + a(3, 3) * s[0],
+            -a(2, 0) * s[4] + a(2, 1) * s[2] - a(2, 3) * s[0],
+
+            -a(1, 0) * c[3] + a(1, 1) * c[1] - a(1, 2) * c[0],
+            a(0, 0) * c[3] - a(0, 1) * c[1] + a(0, 2) * c[0],
+            -a(3, 0) * s[3] + a(3, 1) * s[1] - a(3, 2) * s[0],
+            a(2, 0) * s[3] - a(2, 1) * s[1] + a(2, 2) * s[0],
+        ];
+        Some(Matrix4::new(elems.map(|x| x / det)))
+    }
+
+    /// Determinants of every 2x2 matrix picked from two columns of the top
+    /// two rows (`s`) and of the bottom two rows (`c`)
+    fn pair_dets(&self) -> ([f64; 6], [f64; 6]) {
+        let pair = |r: usize, c0: usize, c1: usize| {
+            self.at(r, c0) * self.at(r + 1, c1) - self.at(r + 1, c0) * self.at(r, c1)
+        };
+        let s = [
+            pair(0, 0, 1),
+            pair(0, 0, 2),
+            pair(0, 0, 3),
+            pair(0, 1, 2),
+            pair(0, 1, 3),
+            pair(0, 2, 3),
+        ];
+        let c = [
+            pair(2, 0, 1),
+            pair(2, 0, 2),
+            pair(2, 0, 3),
+            pair(2, 1, 2),
+            pair(2, 1, 3),
+            pair(2, 2, 3),
+        ];
+        (s, c)
+    }
+}
+
+impl PartialEq for Matrix4 {
+    fn eq(&self, other: &Matrix4) -> bool {
+        elems_eq(&self.elems, &other.elems)
     }
 }
 
-impl PartialEq for Matrix {
-    fn eq(&self, other: &Matrix) -> bool {
-        fn compare_elems(a: &[f64], b: &[f64]) -> bool {
-            for (x, y) in a.iter().zip(b.iter()) {
-                if (x - y).abs() >= EPS {
-                    return false;
-                }
+impl Mul<&Matrix4> for &Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: &Matrix4) -> Matrix4 {
+        let mut elems = [0.0; 16];
+        for r in 0..4 {
+            for c in 0..4 {
+                elems[r * 4 + c] = (0..4).map(|i| self.at(r, i) * other.at(i, c)).sum();
             }
-            true
         }
+        Matrix4::new(elems)
+    }
+}
+
+impl Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
 
-        self.dim == other.dim && compare_elems(&self.elems, &other.elems)
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        &self * &other
     }
 }
 
-impl Mul<Matrix> for Matrix {
-    type Output = Matrix;
+impl Mul<Tuple> for &Matrix4 {
+    type Output = Tuple;
 
-    fn mul(self, other: Matrix) -> Matrix {
-        assert!(self.dim == other.dim);
-        let dot = |r: usize, c: usize| -> f64 {
-            (0..self.dim).map(|x| self.at(r, x) * other.at(x, c)).sum()
+    fn mul(self, t: Tuple) -> Tuple {
+        let dot = |r: usize| -> f64 {
+            self.at(r, 0) * t.0 + self.at(r, 1) * t.1 + self.at(r, 2) * t.2 + self.at(r, 3) * t.3
         };
+        Tuple(dot(0), dot(1), dot(2), dot(3))
+    }
+}
 
-        let mut v = vec![0.0; self.dim * self.dim];
-        for i in 0..self.dim {
-            for j in 0..self.dim {
-                v[j + i * self.dim] = dot(i, j);
-            }
-        }
+impl Mul<Tuple> for Matrix4 {
+    type Output = Tuple;
+
+    fn mul(self, t: Tuple) -> Tuple {
+        &self * t
+    }
+}
+
+impl Mul<Point> for &Matrix4 {
+    type Output = Point;
+
+    fn mul(self, p: Point) -> Point {
+        let t = self * Tuple::point(p.0, p.1, p.2);
+        Point(t.0, t.1, t.2)
+    }
+}
+
+impl Mul<Point> for Matrix4 {
+    type Output = Point;
+
+    fn mul(self, p: Point) -> Point {
+        &self * p
+    }
+}
 
-        Matrix::from_vector(self.dim, &v)
+impl Mul<Vector> for &Matrix4 {
+    type Output = Vector;
+
+    fn mul(self, v: Vector) -> Vector {
+        let t = self * Tuple::vector(v.0, v.1, v.2);
+        Vector(t.0, t.1, t.2)
+    }
+}
+
+impl Mul<Vector> for Matrix4 {
+    type Output = Vector;
+
+    fn mul(self, v: Vector) -> Vector {
+        &self * v
     }
 }
 
@@ -140,10 +334,10 @@ mod tests {
 
     #[test]
     fn constructing_and_inspecting_4x4_matrix() {
-        let elems = vec![
+        let elems = [
             1.0, 2.0, 3.0, 4.0, 5.5, 6.5, 7.5, 8.5, 9.0, 10.0, 11.0, 12.0, 13.5, 14.5, 15.5, 16.5,
         ];
-        let m = Matrix::from_vector(4, &elems);
+        let m = Matrix4::new(elems);
 
         assert_eq!(1.0, m.at(0, 0));
         assert_eq!(4.0, m.at(0, 3));
@@ -156,8 +350,8 @@ mod tests {
 
     #[test]
     fn a_2x2_matrix_ought_to_be_representable() {
-        let elems = vec![-3.0, 5.0, 1.0, -2.0];
-        let m = Matrix::from_vector(2, &elems);
+        let elems = [-3.0, 5.0, 1.0, -2.0];
+        let m = Matrix2::new(elems);
 
         assert_eq!(-3.0, m.at(0, 0));
         assert_eq!(5.0, m.at(0, 1));
@@ -167,8 +361,8 @@ mod tests {
 
     #[test]
     fn a_3x3_matrix_ought_to_be_representable() {
-        let elems = vec![-3.0, 5.0, 0.0, 1.0, -2.0, -7.0, 0.0, 1.0, 1.0];
-        let m = Matrix::from_vector(3, &elems);
+        let elems = [-3.0, 5.0, 0.0, 1.0, -2.0, -7.0, 0.0, 1.0, 1.0];
+        let m = Matrix3::new(elems);
 
         assert_eq!(-3.0, m.at(0, 0));
         assert_eq!(-2.0, m.at(1, 1));
@@ -177,131 +371,131 @@ mod tests {
 
     #[test]
     fn matrix_equality_with_identical_matrices() {
-        let v = vec![
+        let v = [
             1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
         ];
-        let m1 = Matrix::from_vector(4, &v);
-        let m2 = Matrix::from_vector(4, &v);
+        let m1 = Matrix4::new(v);
+        let m2 = Matrix4::new(v);
 
         assert_eq!(m1, m2);
     }
 
     #[test]
     fn matrix_equality_with_different_matrices() {
-        let v1 = vec![
+        let v1 = [
             1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
         ];
-        let v2 = vec![
+        let v2 = [
             2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0,
         ];
 
-        let m1 = Matrix::from_vector(4, &v1);
-        let m2 = Matrix::from_vector(4, &v2);
+        let m1 = Matrix4::new(v1);
+        let m2 = Matrix4::new(v2);
 
         assert_ne!(m1, m2);
     }
 
     #[test]
     fn multiplying_two_matrices() {
-        let v1 = vec![
+        let v1 = [
             1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
         ];
-        let v2 = vec![
+        let v2 = [
             -2.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, -1.0, 4.0, 3.0, 6.0, 5.0, 1.0, 2.0, 7.0, 8.0,
         ];
-        let m1 = Matrix::from_vector(4, &v1);
-        let m2 = Matrix::from_vector(4, &v2);
+        let m1 = Matrix4::new(v1);
+        let m2 = Matrix4::new(v2);
 
-        let v3 = vec![
+        let v3 = [
             20.0, 22.0, 50.0, 48.0, 44.0, 54.0, 114.0, 108.0, 40.0, 58.0, 110.0, 102.0, 16.0, 26.0,
             46.0, 42.0,
         ];
-        let m3 = Matrix::from_vector(4, &v3);
+        let m3 = Matrix4::new(v3);
 
         assert_eq!(m3, m1 * m2);
     }
 
     #[test]
     fn matrix_multiplied_by_tuple() {
-        let v = vec![
+        let v = [
             1.0, 2.0, 3.0, 4.0, 2.0, 4.0, 4.0, 2.0, 8.0, 6.0, 4.0, 1.0, 0.0, 0.0, 0.0, 1.0,
         ];
-        let m = Matrix::from_vector(4, &v);
+        let m = Matrix4::new(v);
         let t = Tuple(1.0, 2.0, 3.0, 1.0);
 
         assert_eq!(Tuple(18.0, 24.0, 33.0, 1.0), m.tuple_prod(t));
+        assert_eq!(Tuple(18.0, 24.0, 33.0, 1.0), m * t);
     }
 
     #[test]
     fn multiplying_matrix_by_identity_matrix() {
-        let v = vec![
+        let v = [
             0.0, 1.0, 2.0, 4.0, 1.0, 2.0, 4.0, 8.0, 2.0, 4.0, 8.0, 16.0, 4.0, 8.0, 16.0, 32.0,
         ];
-        let m = Matrix::from_vector(4, &v);
-        let m2 = Matrix::from_vector(4, &v); // TODO figure out how to deal with borrow checker
-        let p = m2 * Matrix::identity();
+        let m = Matrix4::new(v);
+        let p = m * Matrix4::identity();
 
         assert_eq!(m, p);
     }
 
     #[test]
     fn transposing_matrix() {
-        let v1 = vec![
+        let v1 = [
             0.0, 9.0, 3.0, 0.0, 9.0, 8.0, 0.0, 8.0, 1.0, 8.0, 5.0, 3.0, 0.0, 0.0, 5.0, 8.0,
         ];
-        let m = Matrix::from_vector(4, &v1);
+        let m = Matrix4::new(v1);
 
-        let v2 = vec![
+        let v2 = [
             0.0, 9.0, 1.0, 0.0, 9.0, 8.0, 8.0, 0.0, 3.0, 0.0, 5.0, 5.0, 0.0, 8.0, 3.0, 8.0,
         ];
-        let t = Matrix::from_vector(4, &v2);
+        let t = Matrix4::new(v2);
 
         assert_eq!(t, m.transpose());
     }
 
     #[test]
     fn transposing_identity_matrix() {
-        let i = Matrix::identity();
+        let i = Matrix4::identity();
 
-        assert_eq!(Matrix::identity(), i.transpose());
+        assert_eq!(Matrix4::identity(), i.transpose());
     }
 
     #[test]
     fn calculating_determinant_of_2x2_matrix() {
-        let v = vec![1.0, 5.0, -3.0, 2.0];
-        let m = Matrix::from_vector(2, &v);
+        let v = [1.0, 5.0, -3.0, 2.0];
+        let m = Matrix2::new(v);
 
         assert_eq!(17.0, m.det());
     }
 
     #[test]
     fn submatrix_of_3x3_matrix_is_2x2_matrix() {
-        let v1 = vec![1.0, 5.0, 0.0, -3.0, 2.0, 7.0, 0.0, 6.0, -3.0];
-        let m1 = Matrix::from_vector(3, &v1);
+        let v1 = [1.0, 5.0, 0.0, -3.0, 2.0, 7.0, 0.0, 6.0, -3.0];
+        let m1 = Matrix3::new(v1);
 
-        let v2 = vec![-3.0, 2.0, 0.0, 6.0];
-        let m2 = Matrix::from_vector(2, &v2);
+        let v2 = [-3.0, 2.0, 0.0, 6.0];
+        let m2 = Matrix2::new(v2);
 
         assert_eq!(m2, m1.submatrix(0, 2));
     }
 
     #[test]
     fn submatrix_of_4x4_matrix_is_3x3_matrix() {
-        let v1 = vec![
+        let v1 = [
             -6.0, 1.0, 1.0, 6.0, -8.0, 5.0, 8.0, 6.0, -1.0, 0.0, 8.0, 2.0, -7.0, 1.0, -1.0, 1.0,
         ];
-        let m1 = Matrix::from_vector(4, &v1);
+        let m1 = Matrix4::new(v1);
 
-        let v2 = vec![-6.0, 1.0, 6.0, -8.0, 8.0, 6.0, -7.0, -1.0, 1.0];
-        let m2 = Matrix::from_vector(3, &v2);
+        let v2 = [-6.0, 1.0, 6.0, -8.0, 8.0, 6.0, -7.0, -1.0, 1.0];
+        let m2 = Matrix3::new(v2);
 
         assert_eq!(m2, m1.submatrix(2, 1));
     }
 
     #[test]
     fn calculating_minor_of_3x3_matrix() {
-        let v = vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0];
-        let a = Matrix::from_vector(3, &v);
+        let v = [3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0];
+        let a = Matrix3::new(v);
         let b = a.submatrix(1, 0);
 
         assert_eq!(25.0, b.det());
@@ -310,8 +504,8 @@ mod tests {
 
     #[test]
     fn calculating_cofactor_of_3x3_matrix() {
-        let v = vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0];
-        let a = Matrix::from_vector(3, &v);
+        let v = [3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0];
+        let a = Matrix3::new(v);
 
         assert_eq!(-12.0, a.minor(0, 0));
         assert_eq!(-12.0, a.cofactor(0, 0));
@@ -321,8 +515,8 @@ mod tests {
 
     #[test]
     fn calculating_determinant_of_3x3_matrix() {
-        let v = vec![1.0, 2.0, 6.0, -5.0, 8.0, -4.0, 2.0, 6.0, 4.0];
-        let a = Matrix::from_vector(3, &v);
+        let v = [1.0, 2.0, 6.0, -5.0, 8.0, -4.0, 2.0, 6.0, 4.0];
+        let a = Matrix3::new(v);
 
         assert_eq!(56.0, a.cofactor(0, 0));
         assert_eq!(12.0, a.cofactor(0, 1));
@@ -332,10 +526,10 @@ mod tests {
 
     #[test]
     fn calculating_determinant_of_4x4_matrix() {
-        let v = vec![
+        let v = [
             -2.0, -8.0, 3.0, 5.0, -3.0, 1.0, 7.0, 3.0, 1.0, 2.0, -9.0, 6.0, -6.0, 7.0, 7.0, -9.0,
         ];
-        let a = Matrix::from_vector(4, &v);
+        let a = Matrix4::new(v);
 
         assert_eq!(690.0, a.cofactor(0, 0));
         assert_eq!(447.0, a.cofactor(0, 1));
@@ -346,10 +540,10 @@ mod tests {
 
     #[test]
     fn testing_invertible_matrix_for_invertibility() {
-        let v = vec![
+        let v = [
             6.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 6.0, 4.0, -9.0, 3.0, -7.0, 9.0, 1.0, 7.0, -6.0,
         ];
-        let a = Matrix::from_vector(4, &v);
+        let a = Matrix4::new(v);
 
         assert_eq!(-2120.0, a.det());
         assert!(a.invertible());
@@ -357,10 +551,10 @@ mod tests {
 
     #[test]
     fn testing_non_invertible_matrix_for_invertibility() {
-        let v = vec![
+        let v = [
             -4.0, 2.0, -2.0, -3.0, 9.0, 6.0, 2.0, 6.0, 0.0, -5.0, 1.0, -5.0, 0.0, 0.0, 0.0, 0.0,
         ];
-        let a = Matrix::from_vector(4, &v);
+        let a = Matrix4::new(v);
 
         assert_eq!(0.0, a.det());
         assert!(!a.invertible());
@@ -368,12 +562,12 @@ mod tests {
 
     #[test]
     fn calculating_inverse_of_matrix() {
-        let v1 = vec![
+        let v1 = [
             -5.0, 2.0, 6.0, -8.0, 1.0, -5.0, 1.0, 8.0, 7.0, 7.0, -6.0, -7.0, 1.0, -3.0, 7.0, 4.0,
         ];
-        let a = Matrix::from_vector(4, &v1);
+        let a = Matrix4::new(v1);
 
-        let v2 = vec![
+        let v2 = [
             0.21805, 0.45113, 0.24060, -0.04511, -0.80827, -1.45677, -0.44361, 0.52068, -0.07895,
             -0.22368, -0.05263, 0.19737, -0.52256, -0.81391, -0.30075, 0.30639,
         ];
@@ -384,54 +578,120 @@ mod tests {
         assert_eq!(-160.0 / 532.0, b.at(3, 2));
         assert_eq!(105.0, a.cofactor(3, 2));
         assert_eq!(105.0 / 532.0, b.at(2, 3));
-        assert_eq!(Matrix::from_vector(4, &v2), b);
+        assert_eq!(Matrix4::new(v2), b);
     }
 
     #[test]
     fn calculating_inverse_of_another_matrix() {
-        let v1 = vec![
+        let v1 = [
             8.0, -5.0, 9.0, 2.0, 7.0, 5.0, 6.0, 1.0, -6.0, 0.0, 9.0, 6.0, -3.0, 0.0, -9.0, -4.0,
         ];
-        let a = Matrix::from_vector(4, &v1);
+        let a = Matrix4::new(v1);
 
-        let v2 = vec![
+        let v2 = [
             -0.15385, -0.15385, -0.28205, -0.53846, -0.07692, 0.12308, 0.02564, 0.03077, 0.35897,
             0.35897, 0.43590, 0.92308, -0.69231, -0.69231, -0.76923, -1.92308,
         ];
 
-        assert_eq!(Matrix::from_vector(4, &v2), a.inverse());
+        assert_eq!(Matrix4::new(v2), a.inverse());
     }
 
     #[test]
     fn calculating_inverse_of_third_matrix() {
-        let v1 = vec![
+        let v1 = [
             9.0, 3.0, 0.0, 9.0, -5.0, -2.0, -6.0, -3.0, -4.0, 9.0, 6.0, 4.0, -7.0, 6.0, 6.0, 2.0,
         ];
-        let a = Matrix::from_vector(4, &v1);
+        let a = Matrix4::new(v1);
 
-        let v2 = vec![
+        let v2 = [
             -0.04074, -0.07778, 0.14444, -0.22222, -0.07778, 0.03333, 0.36667, -0.33333, -0.02901,
             -0.14630, -0.10926, 0.12963, 0.17778, 0.06667, -0.26667, 0.33333,
         ];
 
-        assert_eq!(Matrix::from_vector(4, &v2), a.inverse());
+        assert_eq!(Matrix4::new(v2), a.inverse());
     }
 
     #[test]
     fn multiplying_product_by_its_inverse() {
-        let v1 = vec![
+        let v1 = [
             3.0, -9.0, 7.0, 3.0, 3.0, -8.0, 2.0, -9.0, -4.0, 4.0, 4.0, 1.0, -6.0, 5.0, -1.0, 1.0,
         ];
-        let v2 = vec![
+        let v2 = [
             8.0, 2.0, 2.0, 2.0, 3.0, -1.0, 7.0, 0.0, 7.0, 0.0, 5.0, 4.0, 6.0, -2.0, 0.0, 5.0,
         ];
 
-        let a = Matrix::from_vector(4, &v1);
-        let aa = Matrix::from_vector(4, &v1);
-        let b = Matrix::from_vector(4, &v2);
-        let bb = Matrix::from_vector(4, &v2); // TODO figure out the borrowin for Matrix (probably use reference in mul?)
+        let a = Matrix4::new(v1);
+        let b = Matrix4::new(v2);
         let c = a * b;
 
-        assert_eq!(c * bb.inverse(), aa);
+        assert_eq!(c * b.inverse(), a);
+    }
+
+    #[test]
+    fn multiplying_matrices_by_reference() {
+        let a = Matrix4::new([
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
+        ]);
+        let b = Matrix4::new([
+            -2.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, -1.0, 4.0, 3.0, 6.0, 5.0, 1.0, 2.0, 7.0, 8.0,
+        ]);
+
+        let (ra, rb) = (&a, &b);
+
+        assert_eq!(a * b, ra * rb);
+        assert_eq!(
+            a * Tuple(1.0, 2.0, 3.0, 1.0),
+            ra * Tuple(1.0, 2.0, 3.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn closed_form_inverse_matches_cofactor_expansion() {
+        let a = Matrix4::new([
+            9.0, 3.0, 0.0, 9.0, -5.0, -2.0, -6.0, -3.0, -4.0, 9.0, 6.0, 4.0, -7.0, 6.0, 6.0, 2.0,
+        ]);
+        let inv = a.inverse();
+        let det = a.submatrix(0, 0).det() * a.at(0, 0) - a.submatrix(0, 1).det() * a.at(0, 1)
+            + a.submatrix(0, 2).det() * a.at(0, 2)
+            - a.submatrix(0, 3).det() * a.at(0, 3);
+
+        assert!((det - a.det()).abs() < EPS);
+        for r in 0..4 {
+            for c in 0..4 {
+                assert!((a.cofactor(c, r) / det - inv.at(r, c)).abs() < EPS);
+            }
+        }
+        assert_eq!(Matrix4::identity(), a * inv);
+    }
+
+    #[test]
+    fn small_uniform_scaling_is_invertible() {
+        #[rustfmt::skip]
+        let a = Matrix4::new([
+            0.01, 0.0, 0.0, 0.0,
+            0.0, 0.01, 0.0, 0.0,
+            0.0, 0.0, 0.01, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+
+        assert!(a.invertible());
+        assert_eq!(Matrix4::identity(), a * a.inverse());
+    }
+
+    #[test]
+    fn inverting_singular_matrix_fails() {
+        #[rustfmt::skip]
+        let a = Matrix4::new([
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+        let nan = Matrix4::new([f64::NAN; 16]);
+
+        assert!(!a.invertible());
+        assert_eq!(None, a.try_inverse());
+        assert!(!nan.invertible());
+        assert_eq!(None, nan.try_inverse());
     }
 }
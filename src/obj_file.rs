@@ -0,0 +1,553 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::group::{Group, DIVIDE_THRESHOLD};
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::shape::Shape;
+use crate::triangle::{SmoothTriangle, Triangle};
+use crate::vector::Vector;
+use crate::world::World;
+
+/// Wavefront OBJ file parsed into vertices and triangulated faces
+#[derive(Debug, PartialEq)]
+pub struct ObjFile {
+    pub vertices: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub texture_coords: Vec<(f64, f64)>,
+    /// Faces by group, the unnamed default group comes first
+    pub groups: Vec<ObjGroup>,
+    /// Number of unrecognized lines that were skipped
+    pub ignored: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub faces: Vec<ObjFace>,
+}
+
+/// Triangle given as 0-based indices to `ObjFile::vertices` and, if
+/// every corner has one, `ObjFile::normals`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjFace {
+    pub vertices: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ObjError {
+    pub line: usize,
+    pub kind: ObjErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ObjErrorKind {
+    InvalidNumber(String),
+    WrongArgumentCount { expected: usize, found: usize },
+    InvalidIndex(String),
+    IndexOutOfRange(i64),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ObjErrorKind::InvalidNumber(s) => write!(f, "invalid number '{}'", s),
+            ObjErrorKind::WrongArgumentCount { expected, found } => write!(
+                f,
+                "expected at least {} arguments, found {}",
+                expected, found
+            ),
+            ObjErrorKind::InvalidIndex(s) => write!(f, "invalid face index '{}'", s),
+            ObjErrorKind::IndexOutOfRange(i) => write!(f, "index {} out of range", i),
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+impl ObjFile {
+    /// Build the triangles of every group
+    pub fn to_shapes(&self, transform: &Matrix4) -> Vec<Box<dyn Shape>> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.faces.iter())
+            .map(|f| self.face_to_shape(f, transform))
+            .collect()
+    }
+
+    /// Build the triangles of the group called `name`
+    pub fn group_to_shapes(&self, name: &str, transform: &Matrix4) -> Option<Vec<Box<dyn Shape>>> {
+        self.groups.iter().find(|g| g.name == name).map(|g| {
+            g.faces
+                .iter()
+                .map(|f| self.face_to_shape(f, transform))
+                .collect()
+        })
+    }
+
+    /// Build a `Group` placed with `transform`, holding the faces of the
+    /// default group directly and every named group as a nested group.
+    /// Large groups are divided into a bounding volume hierarchy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible; see `Shape::set_transform`.
+    pub fn to_group(&self, transform: &Matrix4) -> Group {
+        let identity = Matrix4::identity();
+        let mut group = Group::new();
+        group.set_transform(*transform);
+        for g in &self.groups {
+            if g.name.is_empty() {
+                for f in &g.faces {
+                    group.add_boxed_child(self.face_to_shape(f, &identity));
+                }
+            } else if !g.faces.is_empty() {
+                let mut subgroup = Group::new();
+                for f in &g.faces {
+                    subgroup.add_boxed_child(self.face_to_shape(f, &identity));
+                }
+                group.add_child(subgroup);
+            }
+        }
+        group.divide(DIVIDE_THRESHOLD);
+        group
+    }
+
+    /// Add the mesh to `world` as a single group placed with `transform`,
+    /// which must be invertible as for `to_group`
+    pub fn add_to_world(&self, world: &mut World, transform: &Matrix4) {
+        world.add_object(self.to_group(transform));
+    }
+
+    fn face_to_shape(&self, face: &ObjFace, transform: &Matrix4) -> Box<dyn Shape> {
+        let [p1, p2, p3] = face.vertices.map(|i| self.vertices[i]);
+        match face.normals {
+            Some(normals) => {
+                let [n1, n2, n3] = normals.map(|i| self.normals[i]);
+                let mut t = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
+                t.set_transform(*transform);
+                Box::new(t)
+            }
+            None => {
+                let mut t = Triangle::new(p1, p2, p3);
+                t.set_transform(*transform);
+                Box::new(t)
+            }
+        }
+    }
+}
+
+pub fn parse_obj(input: &str) -> Result<ObjFile, ObjError> {
+    let mut obj = ObjFile {
+        vertices: vec![],
+        normals: vec![],
+        texture_coords: vec![],
+        groups: vec![ObjGroup {
+            name: String::new(),
+            faces: vec![],
+        }],
+        ignored: 0,
+    };
+    let mut current = 0;
+
+    for (n, line) in input.lines().enumerate() {
+        let line_no = n + 1;
+        let err = |kind| ObjError {
+            line: line_no,
+            kind,
+        };
+        let mut words = line.split_whitespace();
+        let keyword = words.next();
+        let args: Vec<&str> = words.collect();
+
+        match keyword {
+            None => continue,
+            Some(w) if w.starts_with('#') => continue,
+            Some("v") => {
+                let [x, y, z] = parse_numbers::<3>(&args).map_err(err)?;
+                obj.vertices.push(Point::new(x, y, z));
+            }
+            Some("vn") => {
+                let [x, y, z] = parse_numbers::<3>(&args).map_err(err)?;
+                obj.normals.push(Vector::new(x, y, z));
+            }
+            Some("vt") => {
+                // v and the depth w are optional; w is checked but not used
+                let [u, v, _] = parse_numbers_with_defaults::<3>(&args, 1).map_err(err)?;
+                obj.texture_coords.push((u, v));
+            }
+            Some("f") => {
+                if args.len() < 3 {
+                    return Err(err(ObjErrorKind::WrongArgumentCount {
+                        expected: 3,
+                        found: args.len(),
+                    }));
+                }
+                let corners = args
+                    .iter()
+                    .map(|a| parse_corner(a, &obj))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(err)?;
+
+                // fan triangulation around the first corner
+                for i in 1..corners.len() - 1 {
+                    let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                    let normals = match (a.1, b.1, c.1) {
+                        (Some(na), Some(nb), Some(nc)) => Some([na, nb, nc]),
+                        _ => None,
+                    };
+                    obj.groups[current].faces.push(ObjFace {
+                        vertices: [a.0, b.0, c.0],
+                        normals,
+                    });
+                }
+            }
+            Some("g") => {
+                let name = args.join(" ");
+                current = match obj.groups.iter().position(|g| g.name == name) {
+                    Some(i) => i,
+                    None => {
+                        obj.groups.push(ObjGroup {
+                            name,
+                            faces: vec![],
+                        });
+                        obj.groups.len() - 1
+                    }
+                };
+            }
+            Some(_) => obj.ignored += 1,
+        }
+    }
+
+    Ok(obj)
+}
+
+/// Parse the first `N` arguments as numbers; any further ones are ignored
+fn parse_numbers<const N: usize>(args: &[&str]) -> Result<[f64; N], ObjErrorKind> {
+    parse_numbers_with_defaults(args, N)
+}
+
+/// Like `parse_numbers`, but only the first `required` numbers must be
+/// present and missing ones after them are 0
+fn parse_numbers_with_defaults<const N: usize>(
+    args: &[&str],
+    required: usize,
+) -> Result<[f64; N], ObjErrorKind> {
+    if args.len() < required {
+        return Err(ObjErrorKind::WrongArgumentCount {
+            expected: required,
+            found: args.len(),
+        });
+    }
+
+    let mut v = [0.0; N];
+    for (x, a) in v.iter_mut().zip(args) {
+        *x = a
+            .parse()
+            .map_err(|_| ObjErrorKind::InvalidNumber(a.to_string()))?;
+    }
+    Ok(v)
+}
+
+/// Parse a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into 0-based
+/// vertex and normal indices
+fn parse_corner(corner: &str, obj: &ObjFile) -> Result<(usize, Option<usize>), ObjErrorKind> {
+    let mut parts = corner.split('/');
+    let vertex = parts.next().unwrap_or("");
+    let texture = parts.next().unwrap_or("");
+    let normal = parts.next().unwrap_or("");
+    if parts.next().is_some() {
+        return Err(ObjErrorKind::InvalidIndex(corner.to_string()));
+    }
+
+    let v = resolve_index(vertex, corner, obj.vertices.len())?;
+    if !texture.is_empty() {
+        resolve_index(texture, corner, obj.texture_coords.len())?;
+    }
+    let n = if normal.is_empty() {
+        None
+    } else {
+        Some(resolve_index(normal, corner, obj.normals.len())?)
+    };
+    Ok((v, n))
+}
+
+/// OBJ indices are 1-based, negative ones count back from the latest element
+fn resolve_index(index: &str, corner: &str, len: usize) -> Result<usize, ObjErrorKind> {
+    let i: i64 = index
+        .parse()
+        .map_err(|_| ObjErrorKind::InvalidIndex(corner.to_string()))?;
+    let resolved = if i < 0 { len as i64 + i } else { i - 1 };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(ObjErrorKind::IndexOutOfRange(i));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transformation;
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let obj = parse_obj(gibberish).unwrap();
+
+        assert_eq!(5, obj.ignored);
+    }
+
+    #[test]
+    fn vertex_records() {
+        let file = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+        let obj = parse_obj(file).unwrap();
+
+        assert_eq!(Point::new(-1.0, 1.0, 0.0), obj.vertices[0]);
+        assert_eq!(Point::new(-1.0, 0.5, 0.0), obj.vertices[1]);
+        assert_eq!(Point::new(1.0, 0.0, 0.0), obj.vertices[2]);
+        assert_eq!(Point::new(1.0, 1.0, 0.0), obj.vertices[3]);
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let obj = parse_obj(file).unwrap();
+        let faces = &obj.groups[0].faces;
+
+        assert_eq!(0, obj.ignored);
+        assert_eq!([0, 1, 2], faces[0].vertices);
+        assert_eq!([0, 2, 3], faces[1].vertices);
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let obj = parse_obj(file).unwrap();
+        let shapes = obj.to_shapes(&Matrix4::identity());
+        let t = |i: usize| shapes[i].as_any().downcast_ref::<Triangle>().unwrap();
+
+        assert_eq!(3, shapes.len());
+        assert_eq!(obj.vertices[0], t(0).p1);
+        assert_eq!(obj.vertices[1], t(0).p2);
+        assert_eq!(obj.vertices[2], t(0).p3);
+        assert_eq!(obj.vertices[0], t(1).p1);
+        assert_eq!(obj.vertices[2], t(1).p2);
+        assert_eq!(obj.vertices[3], t(1).p3);
+        assert_eq!(obj.vertices[0], t(2).p1);
+        assert_eq!(obj.vertices[3], t(2).p2);
+        assert_eq!(obj.vertices[4], t(2).p3);
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj(file).unwrap();
+        let g1 = obj
+            .group_to_shapes("FirstGroup", &Matrix4::identity())
+            .unwrap();
+        let g2 = obj
+            .group_to_shapes("SecondGroup", &Matrix4::identity())
+            .unwrap();
+        let t1 = g1[0].as_any().downcast_ref::<Triangle>().unwrap();
+        let t2 = g2[0].as_any().downcast_ref::<Triangle>().unwrap();
+
+        assert_eq!(1, g1.len());
+        assert_eq!(1, g2.len());
+        assert_eq!(obj.vertices[0], t1.p1);
+        assert_eq!(obj.vertices[1], t1.p2);
+        assert_eq!(obj.vertices[2], t1.p3);
+        assert_eq!(obj.vertices[0], t2.p1);
+        assert_eq!(obj.vertices[2], t2.p2);
+        assert_eq!(obj.vertices[3], t2.p3);
+        assert!(obj
+            .group_to_shapes("ThirdGroup", &Matrix4::identity())
+            .is_none());
+    }
+
+    #[test]
+    fn converting_obj_file_to_group() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj(file).unwrap();
+        let g = obj.to_group(&Matrix4::identity());
+
+        assert_eq!(2, g.children().len());
+        for child in g.children() {
+            let sub = child.as_any().downcast_ref::<Group>().unwrap();
+            assert_eq!(1, sub.children().len());
+        }
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let file = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3";
+        let obj = parse_obj(file).unwrap();
+
+        assert_eq!(Vector::new(0.0, 0.0, 1.0), obj.normals[0]);
+        assert_eq!(Vector::new(0.707, 0.0, -0.707), obj.normals[1]);
+        assert_eq!(Vector::new(1.0, 2.0, 3.0), obj.normals[2]);
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let file = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vt 0 0
+vt 1 0
+vt 0 1
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/1/3 2/2/1 3/3/2";
+        let obj = parse_obj(file).unwrap();
+        let shapes = obj.to_shapes(&Matrix4::identity());
+        let t1 = shapes[0].as_any().downcast_ref::<SmoothTriangle>().unwrap();
+        let t2 = shapes[1].as_any().downcast_ref::<SmoothTriangle>().unwrap();
+
+        assert_eq!(obj.vertices[0], t1.p1);
+        assert_eq!(obj.vertices[1], t1.p2);
+        assert_eq!(obj.vertices[2], t1.p3);
+        assert_eq!(obj.normals[2], t1.n1);
+        assert_eq!(obj.normals[0], t1.n2);
+        assert_eq!(obj.normals[1], t1.n3);
+        assert_eq!(t1, t2);
+    }
+
+    #[test]
+    fn texture_coordinate_records() {
+        let obj = parse_obj("vt 0.5 0.25\nvt 1 0 0\nvt 0.75").unwrap();
+
+        assert_eq!(
+            vec![(0.5, 0.25), (1.0, 0.0), (0.75, 0.0)],
+            obj.texture_coords
+        );
+        assert_eq!(
+            ObjError {
+                line: 1,
+                kind: ObjErrorKind::WrongArgumentCount {
+                    expected: 1,
+                    found: 0
+                }
+            },
+            parse_obj("vt").unwrap_err()
+        );
+        assert_eq!(
+            ObjErrorKind::InvalidNumber("x".to_string()),
+            parse_obj("vt 0 0 x").unwrap_err().kind
+        );
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let file = "v 0 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1";
+        let obj = parse_obj(file).unwrap();
+
+        assert_eq!([0, 1, 2], obj.groups[0].faces[0].vertices);
+    }
+
+    #[test]
+    fn malformed_number_is_reported_with_line_number() {
+        let file = "v 0 1 0\nv -1 zero 0";
+        let err = parse_obj(file).unwrap_err();
+
+        assert_eq!(2, err.line);
+        assert_eq!(ObjErrorKind::InvalidNumber("zero".to_string()), err.kind);
+        assert_eq!("line 2: invalid number 'zero'", err.to_string());
+    }
+
+    #[test]
+    fn missing_coordinates_are_reported() {
+        let err = parse_obj("v 1 2").unwrap_err();
+
+        assert_eq!(1, err.line);
+        assert_eq!(
+            ObjErrorKind::WrongArgumentCount {
+                expected: 3,
+                found: 2
+            },
+            err.kind
+        );
+    }
+
+    #[test]
+    fn face_referring_to_missing_vertex_is_reported() {
+        let file = "v 0 1 0\nv -1 0 0\n\nf 1 2 3";
+        let err = parse_obj(file).unwrap_err();
+
+        assert_eq!(4, err.line);
+        assert_eq!(ObjErrorKind::IndexOutOfRange(3), err.kind);
+    }
+
+    #[test]
+    fn malformed_face_index_is_reported() {
+        let file = "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 x/1";
+        let err = parse_obj(file).unwrap_err();
+
+        assert_eq!(4, err.line);
+        assert_eq!(ObjErrorKind::InvalidIndex("x/1".to_string()), err.kind);
+    }
+
+    #[test]
+    fn adding_mesh_to_world_with_transform() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 3 4";
+        let obj = parse_obj(file).unwrap();
+        let mut w = World::new();
+        obj.add_to_world(&mut w, &transformation::translation(0.0, 0.0, 5.0));
+
+        assert_eq!(1, w.objects.len());
+        let r = crate::ray::Ray::new(Point::new(0.5, 0.8, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(1, xs.len());
+        assert_eq!(10.0, xs[0].t);
+    }
+}
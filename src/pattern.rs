@@ -0,0 +1,406 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+use crate::color::Color;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::shape::Shape;
+use crate::transformation::Transform;
+
+/// Color that varies over a surface.
+///
+/// `pattern_at` works in pattern space; the pattern's own `transform()` is
+/// applied on top of the transform of the object it is attached to.
+pub trait Pattern: Any + Debug + Send + Sync {
+    fn transformation(&self) -> &Transform;
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    fn set_transform(&mut self, transform: Matrix4);
+    fn pattern_at(&self, point: Point) -> Color;
+
+    fn as_any(&self) -> &dyn Any;
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool;
+
+    fn transform(&self) -> &Matrix4 {
+        self.transformation().matrix()
+    }
+
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: Point) -> Color {
+        let object_point = object.world_to_object(world_point);
+        let pattern_point = self.transformation().inverse() * object_point;
+        self.pattern_at(pattern_point)
+    }
+}
+
+impl<'a> PartialEq for dyn Pattern + 'a {
+    fn eq(&self, other: &(dyn Pattern + 'a)) -> bool {
+        self.pattern_eq(other)
+    }
+}
+
+/// Helper for implementing `Pattern::pattern_eq` for patterns that are `PartialEq`.
+pub fn pattern_eq<T: Pattern + PartialEq>(a: &T, b: &dyn Pattern) -> bool {
+    b.as_any().downcast_ref::<T>().is_some_and(|b| a == b)
+}
+
+/// Alternating stripes of `a` and `b` along the x axis
+#[derive(Debug, PartialEq)]
+pub struct StripePattern {
+    pub a: Color,
+    pub b: Color,
+    transform: Transform,
+}
+
+impl StripePattern {
+    pub fn new(a: Color, b: Color) -> StripePattern {
+        StripePattern {
+            a,
+            b,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for StripePattern {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        if point.0.floor().rem_euclid(2.0) == 0.0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        pattern_eq(self, other)
+    }
+}
+
+/// Linear blend from `a` to `b` along the x axis, repeating every unit
+#[derive(Debug, PartialEq)]
+pub struct GradientPattern {
+    pub a: Color,
+    pub b: Color,
+    transform: Transform,
+}
+
+impl GradientPattern {
+    pub fn new(a: Color, b: Color) -> GradientPattern {
+        GradientPattern {
+            a,
+            b,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for GradientPattern {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let distance = self.b - self.a;
+        let fraction = point.0 - point.0.floor();
+        self.a + distance * fraction
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        pattern_eq(self, other)
+    }
+}
+
+/// Concentric rings of `a` and `b` around the y axis
+#[derive(Debug, PartialEq)]
+pub struct RingPattern {
+    pub a: Color,
+    pub b: Color,
+    transform: Transform,
+}
+
+impl RingPattern {
+    pub fn new(a: Color, b: Color) -> RingPattern {
+        RingPattern {
+            a,
+            b,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for RingPattern {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let distance = (point.0 * point.0 + point.2 * point.2).sqrt();
+        if distance.floor().rem_euclid(2.0) == 0.0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        pattern_eq(self, other)
+    }
+}
+
+/// Three dimensional checkerboard of unit cubes
+#[derive(Debug, PartialEq)]
+pub struct CheckersPattern {
+    pub a: Color,
+    pub b: Color,
+    transform: Transform,
+}
+
+impl CheckersPattern {
+    pub fn new(a: Color, b: Color) -> CheckersPattern {
+        CheckersPattern {
+            a,
+            b,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for CheckersPattern {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let sum = point.0.floor() + point.1.floor() + point.2.floor();
+        if sum.rem_euclid(2.0) == 0.0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        pattern_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::transformation;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestPattern {
+        transform: Transform,
+    }
+
+    impl Pattern for TestPattern {
+        fn transformation(&self) -> &Transform {
+            &self.transform
+        }
+
+        fn set_transform(&mut self, transform: Matrix4) {
+            self.transform = Transform::new(transform);
+        }
+
+        fn pattern_at(&self, point: Point) -> Color {
+            Color::new(point.0, point.1, point.2)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+            pattern_eq(self, other)
+        }
+    }
+
+    #[test]
+    fn creating_stripe_pattern() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(white(), pattern.a);
+        assert_eq!(black(), pattern.b);
+    }
+
+    #[test]
+    fn stripe_pattern_is_constant_in_y() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 1.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn stripe_pattern_is_constant_in_z() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 1.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 2.0)));
+    }
+
+    #[test]
+    fn stripe_pattern_alternates_in_x() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.9, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(1.0, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(-0.1, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(-1.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(-1.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn pattern_with_object_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        let pattern = TestPattern {
+            transform: Transform::identity(),
+        };
+        let c = pattern.pattern_at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+
+        assert_eq!(Color::new(1.0, 1.5, 2.0), c);
+    }
+
+    #[test]
+    fn pattern_with_pattern_transformation() {
+        let shape = Sphere::new();
+        let pattern = TestPattern {
+            transform: Transform::new(transformation::scaling(2.0, 2.0, 2.0)),
+        };
+        let c = pattern.pattern_at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+
+        assert_eq!(Color::new(1.0, 1.5, 2.0), c);
+    }
+
+    #[test]
+    fn pattern_with_both_object_and_pattern_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        let pattern = TestPattern {
+            transform: Transform::new(transformation::translation(0.5, 1.0, 1.5)),
+        };
+        let c = pattern.pattern_at_shape(&shape, Point::new(2.5, 3.0, 3.5));
+
+        assert_eq!(Color::new(0.75, 0.5, 0.25), c);
+    }
+
+    #[test]
+    fn pattern_caches_inverse_of_its_transform() {
+        let mut pattern = StripePattern::new(white(), black());
+        pattern.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+
+        assert_eq!(transformation::scaling(2.0, 2.0, 2.0), *pattern.transform());
+        assert_eq!(
+            transformation::scaling(0.5, 0.5, 0.5),
+            *pattern.transformation().inverse()
+        );
+    }
+
+    #[test]
+    fn gradient_linearly_interpolates_between_colors() {
+        let pattern = GradientPattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            pattern.pattern_at(Point::new(0.25, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            pattern.pattern_at(Point::new(0.5, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.25, 0.25, 0.25),
+            pattern.pattern_at(Point::new(0.75, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn ring_should_extend_in_both_x_and_z() {
+        let pattern = RingPattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(1.0, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(0.0, 0.0, 1.0)));
+        // 0.708 = just slightly more than sqrt(2)/2
+        assert_eq!(black(), pattern.pattern_at(Point::new(0.708, 0.0, 0.708)));
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_x() {
+        let pattern = CheckersPattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.99, 0.0, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(1.01, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_y() {
+        let pattern = CheckersPattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.99, 0.0)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(0.0, 1.01, 0.0)));
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_z() {
+        let pattern = CheckersPattern::new(white(), black());
+
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(white(), pattern.pattern_at(Point::new(0.0, 0.0, 0.99)));
+        assert_eq!(black(), pattern.pattern_at(Point::new(0.0, 0.0, 1.01)));
+    }
+}
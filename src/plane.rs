@@ -0,0 +1,162 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+use crate::EPSILON;
+
+/// Infinite plane, the xz plane in object space
+#[derive(Debug, PartialEq)]
+pub struct Plane {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+}
+
+impl Plane {
+    pub fn new() -> Plane {
+        Plane {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+        }
+    }
+}
+
+impl Shape for Plane {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if ray.direction.1.abs() < EPSILON {
+            vec![]
+        } else {
+            let t = -ray.origin.1 / ray.direction.1;
+            vec![Intersection::new(t, self)]
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Plane {
+        Plane::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    #[test]
+    fn normal_of_plane_is_constant_everywhere() {
+        let p = Plane::new();
+        let n1 = p.local_normal_at(Point::new(0.0, 0.0, 0.0));
+        let n2 = p.local_normal_at(Point::new(10.0, 0.0, -10.0));
+        let n3 = p.local_normal_at(Point::new(-5.0, 0.0, 150.0));
+
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), n1);
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), n2);
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), n3);
+    }
+
+    #[test]
+    fn intersect_with_ray_parallel_to_plane() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = p.local_intersect(&r);
+
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn intersect_with_coplanar_ray() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = p.local_intersect(&r);
+
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn ray_intersecting_plane_from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].t);
+        assert_eq!(&p as &dyn Shape, xs[0].object);
+    }
+
+    #[test]
+    fn ray_intersecting_plane_from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = p.local_intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].t);
+        assert_eq!(&p as &dyn Shape, xs[0].object);
+    }
+
+    #[test]
+    fn plane_has_bounding_box() {
+        let b = Plane::new().bounds();
+
+        assert_eq!(f64::NEG_INFINITY, b.min.0);
+        assert_eq!(0.0, b.min.1);
+        assert_eq!(f64::NEG_INFINITY, b.min.2);
+        assert_eq!(f64::INFINITY, b.max.0);
+        assert_eq!(0.0, b.max.1);
+        assert_eq!(f64::INFINITY, b.max.2);
+    }
+}
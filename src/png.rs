@@ -0,0 +1,617 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::deflate::{self, Compression, InflateError};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Image data is split into IDAT chunks of at most this many bytes
+const IDAT_SIZE: usize = 1 << 20;
+
+const GRAYSCALE: u8 = 0;
+const RGB: u8 = 2;
+const PALETTE: u8 = 3;
+const GRAYSCALE_ALPHA: u8 = 4;
+const RGB_ALPHA: u8 = 6;
+
+/// Bits per color channel in an encoded image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PngError {
+    /// Byte offset of the chunk (or signature) that could not be read
+    pub offset: usize,
+    pub kind: PngErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PngErrorKind {
+    NotPng,
+    UnexpectedEnd,
+    ChecksumMismatch(String),
+    MissingHeader,
+    InvalidHeader,
+    TooLarge,
+    Unsupported(String),
+    MissingPalette,
+    PaletteIndexOutOfRange(u8),
+    MissingImageData,
+    Inflate(InflateError),
+    InvalidFilter(u8),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            PngErrorKind::NotPng => write!(f, "not a PNG file"),
+            PngErrorKind::UnexpectedEnd => write!(f, "file ends unexpectedly"),
+            PngErrorKind::ChecksumMismatch(chunk) => write!(f, "bad checksum in {} chunk", chunk),
+            PngErrorKind::MissingHeader => write!(f, "IHDR chunk is missing"),
+            PngErrorKind::InvalidHeader => write!(f, "invalid IHDR chunk"),
+            PngErrorKind::TooLarge => write!(f, "image is too large"),
+            PngErrorKind::Unsupported(feature) => write!(f, "unsupported {}", feature),
+            PngErrorKind::MissingPalette => write!(f, "PLTE chunk is missing"),
+            PngErrorKind::PaletteIndexOutOfRange(i) => {
+                write!(f, "palette index {} out of range", i)
+            }
+            PngErrorKind::MissingImageData => write!(f, "image data is missing or too short"),
+            PngErrorKind::Inflate(e) => write!(f, "{}", e),
+            PngErrorKind::InvalidFilter(filter) => write!(f, "invalid filter type {}", filter),
+        }
+    }
+}
+
+impl Error for PngError {}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// CRC-32 of the concatenation of `parts`, as used for PNG chunks
+pub fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut c = 0xffff_ffffu32;
+    for &byte in parts.iter().flat_map(|p| p.iter()) {
+        c = CRC_TABLE[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    c ^ 0xffff_ffff
+}
+
+/// Color component clamped to 0..=1 and scaled to 0..=`max`
+fn quantize(x: f64, max: f64) -> u16 {
+    (x * max).round().clamp(0.0, max) as u16
+}
+
+/// Encode `canvas` as an RGB PNG image
+pub fn encode(canvas: &Canvas, depth: BitDepth) -> Vec<u8> {
+    let (bits, bytes_per_pixel) = match depth {
+        BitDepth::Eight => (8, 3),
+        BitDepth::Sixteen => (16, 6),
+    };
+
+    let stride = canvas.width * bytes_per_pixel;
+    let mut filtered = Vec::with_capacity((stride + 1) * canvas.height);
+    let mut previous = vec![0; stride];
+    let mut row = Vec::with_capacity(stride);
+    for y in 0..canvas.height {
+        row.clear();
+        for x in 0..canvas.width {
+            let c = canvas.pixel_at(x, y);
+            for v in [c.0, c.1, c.2].iter() {
+                match depth {
+                    BitDepth::Eight => row.push(quantize(*v, 255.0) as u8),
+                    BitDepth::Sixteen => {
+                        row.extend_from_slice(&quantize(*v, 65535.0).to_be_bytes())
+                    }
+                }
+            }
+        }
+        filter_row(&row, &previous, bytes_per_pixel, &mut filtered);
+        std::mem::swap(&mut row, &mut previous);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(canvas.width as u32).to_be_bytes());
+    header.extend_from_slice(&(canvas.height as u32).to_be_bytes());
+    // bit depth, color type, compression, filter and interlace methods
+    header.extend_from_slice(&[bits, RGB, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    let data = deflate::zlib_compress(&filtered, Compression::Compressed);
+    for part in data.chunks(IDAT_SIZE) {
+        write_chunk(&mut png, b"IDAT", part);
+    }
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Append `row` to `out` with whichever filter is likely to compress best,
+/// judged by the smallest sum of the filtered bytes taken as signed
+fn filter_row(row: &[u8], previous: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let left = |i: usize| if i >= bpp { row[i - bpp] } else { 0 };
+    let up_left = |i: usize| if i >= bpp { previous[i - bpp] } else { 0 };
+    let filter = |kind: u8, i: usize| -> u8 {
+        let x = row[i];
+        match kind {
+            0 => x,
+            1 => x.wrapping_sub(left(i)),
+            2 => x.wrapping_sub(previous[i]),
+            3 => x.wrapping_sub(((left(i) as u16 + previous[i] as u16) / 2) as u8),
+            _ => x.wrapping_sub(paeth(left(i), previous[i], up_left(i))),
+        }
+    };
+
+    let cost = |kind: u8| -> u64 {
+        (0..row.len())
+            .map(|i| (filter(kind, i) as i8).unsigned_abs() as u64)
+            .sum()
+    };
+    let best = (0..5).min_by_key(|&kind| cost(kind)).unwrap_or(0);
+    out.push(best);
+    out.extend((0..row.len()).map(|i| filter(best, i)));
+}
+
+/// Image header fields that decoding depends on
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            GRAYSCALE | PALETTE => 1,
+            GRAYSCALE_ALPHA => 2,
+            RGB => 3,
+            _ => 4,
+        }
+    }
+}
+
+/// Decode a PNG image. Transparency is ignored, and every color type and
+/// bit depth is read except interlaced images.
+pub fn decode(png: &[u8]) -> Result<Canvas, PngError> {
+    let error = |offset, kind| PngError { offset, kind };
+    if !png.starts_with(&SIGNATURE) {
+        return Err(error(0, PngErrorKind::NotPng));
+    }
+
+    let mut header = None;
+    let mut palette: Option<&[u8]> = None;
+    let mut data = vec![];
+    let mut data_offset = None;
+    let mut offset = SIGNATURE.len();
+    loop {
+        let chunk_error = |kind| error(offset, kind);
+        let length = png
+            .get(offset..offset + 4)
+            .ok_or_else(|| chunk_error(PngErrorKind::UnexpectedEnd))?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let kind = png
+            .get(offset + 4..offset + 8)
+            .ok_or_else(|| chunk_error(PngErrorKind::UnexpectedEnd))?;
+        let body = png
+            .get(offset + 8..offset + 8 + length)
+            .ok_or_else(|| chunk_error(PngErrorKind::UnexpectedEnd))?;
+        let crc = png
+            .get(offset + 8 + length..offset + 12 + length)
+            .ok_or_else(|| chunk_error(PngErrorKind::UnexpectedEnd))?;
+        let name = String::from_utf8_lossy(kind).into_owned();
+        if u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) != crc32(&[kind, body]) {
+            return Err(chunk_error(PngErrorKind::ChecksumMismatch(name)));
+        }
+
+        match kind {
+            b"IHDR" => header = Some(read_header(body).map_err(chunk_error)?),
+            _ if header.is_none() => return Err(chunk_error(PngErrorKind::MissingHeader)),
+            b"PLTE" => palette = Some(body),
+            b"IDAT" => {
+                data_offset.get_or_insert(offset);
+                data.extend_from_slice(body);
+            }
+            b"IEND" => break,
+            // ancillary chunks have a lowercase first letter and may be skipped
+            _ if kind[0].is_ascii_lowercase() => (),
+            _ => return Err(chunk_error(PngErrorKind::Unsupported(name + " chunk"))),
+        }
+        offset += 12 + length;
+    }
+
+    let header = header.ok_or_else(|| error(offset, PngErrorKind::MissingHeader))?;
+    let data_offset = data_offset.ok_or_else(|| error(offset, PngErrorKind::MissingImageData))?;
+    let data_error = |kind| error(data_offset, kind);
+    let data = deflate::zlib_decompress(&data).map_err(|e| data_error(PngErrorKind::Inflate(e)))?;
+    let samples = unfilter(&header, &data).map_err(data_error)?;
+
+    let palette = match (header.color_type, palette) {
+        (PALETTE, None) => return Err(data_error(PngErrorKind::MissingPalette)),
+        (_, palette) => palette.unwrap_or(&[]),
+    };
+    let max = ((1u32 << header.bit_depth) - 1) as f64;
+    let channels = header.channels();
+    let mut canvas = Canvas::new(header.width, header.height);
+    for y in 0..header.height {
+        for x in 0..header.width {
+            let s = &samples[(y * header.width + x) * channels..][..channels];
+            let color = match header.color_type {
+                GRAYSCALE | GRAYSCALE_ALPHA => {
+                    let v = s[0] as f64 / max;
+                    Color::new(v, v, v)
+                }
+                PALETTE => {
+                    let i = s[0] as usize;
+                    let rgb = palette.get(i * 3..i * 3 + 3).ok_or_else(|| {
+                        data_error(PngErrorKind::PaletteIndexOutOfRange(s[0] as u8))
+                    })?;
+                    Color::new(
+                        rgb[0] as f64 / 255.0,
+                        rgb[1] as f64 / 255.0,
+                        rgb[2] as f64 / 255.0,
+                    )
+                }
+                _ => Color::new(s[0] as f64 / max, s[1] as f64 / max, s[2] as f64 / max),
+            };
+            canvas.write_pixel(x, y, color);
+        }
+    }
+    Ok(canvas)
+}
+
+fn read_header(body: &[u8]) -> Result<Header, PngErrorKind> {
+    if body.len() != 13 {
+        return Err(PngErrorKind::InvalidHeader);
+    }
+    let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize;
+    let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize;
+    // the format limits both to 2^31 - 1
+    let valid_size = [width, height]
+        .iter()
+        .all(|n| (1..=i32::MAX as usize).contains(n));
+    let (bit_depth, color_type) = (body[8], body[9]);
+    let valid_depth = match color_type {
+        GRAYSCALE => [1, 2, 4, 8, 16].contains(&bit_depth),
+        PALETTE => [1, 2, 4, 8].contains(&bit_depth),
+        RGB | GRAYSCALE_ALPHA | RGB_ALPHA => [8, 16].contains(&bit_depth),
+        _ => false,
+    };
+    if !valid_size || !valid_depth || body[10] != 0 || body[11] != 0 {
+        return Err(PngErrorKind::InvalidHeader);
+    }
+    if body[12] != 0 {
+        return Err(PngErrorKind::Unsupported("interlaced image".to_string()));
+    }
+    Ok(Header {
+        width,
+        height,
+        bit_depth,
+        color_type,
+    })
+}
+
+/// Undo the filtering of every row and split the rows into samples
+fn unfilter(header: &Header, data: &[u8]) -> Result<Vec<u16>, PngErrorKind> {
+    let bits_per_pixel = header.channels() * header.bit_depth as usize;
+    let stride = header
+        .width
+        .checked_mul(bits_per_pixel)
+        .ok_or(PngErrorKind::TooLarge)?
+        .div_ceil(8);
+    let bpp = (bits_per_pixel / 8).max(1);
+    let size = (stride + 1)
+        .checked_mul(header.height)
+        .ok_or(PngErrorKind::TooLarge)?;
+    if data.len() < size {
+        return Err(PngErrorKind::MissingImageData);
+    }
+
+    // width and height are below 2^31 and the rows are all there, so this
+    // neither overflows nor allocates much more than the data itself
+    let count = header.width * header.channels();
+    let mut samples = Vec::with_capacity(count * header.height);
+    let mut previous = vec![0u8; stride];
+    let mut row = vec![0u8; stride];
+    for line in data.chunks_exact(stride + 1).take(header.height) {
+        let (filter, filtered) = (line[0], &line[1..]);
+        for i in 0..stride {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+            let up = previous[i];
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(PngErrorKind::InvalidFilter(filter)),
+            };
+            row[i] = filtered[i].wrapping_add(predicted);
+        }
+
+        match header.bit_depth {
+            16 => samples.extend(
+                row.chunks_exact(2)
+                    .take(count)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]])),
+            ),
+            8 => samples.extend(row.iter().take(count).map(|&b| b as u16)),
+            depth => {
+                // several samples packed into each byte, most significant first
+                let per_byte = 8 / depth as usize;
+                let mask = (1u16 << depth) - 1;
+                samples.extend((0..count).map(|i| {
+                    let byte = row[i / per_byte] as u16;
+                    let shift = 8 - depth as usize * (i % per_byte + 1);
+                    (byte >> shift) & mask
+                }));
+            }
+        }
+        std::mem::swap(&mut row, &mut previous);
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_canvas(width: usize, height: usize) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        let mut seed = 7u32;
+        for y in 0..height {
+            for x in 0..width {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let noise = (seed >> 16) as f64 / 65536.0;
+                // smooth areas, noise and out of range values
+                let c = match (x + y) % 3 {
+                    0 => Color::new(x as f64 / width as f64, y as f64 / height as f64, 0.5),
+                    1 => Color::new(noise, 1.0 - noise, noise * noise),
+                    _ => Color::new(1.5, -0.5, noise),
+                };
+                canvas.write_pixel(x, y, c);
+            }
+        }
+        canvas
+    }
+
+    fn assert_quantized_equal(expected: &Canvas, actual: &Canvas, max: f64) {
+        assert_eq!(expected.width, actual.width);
+        assert_eq!(expected.height, actual.height);
+        for y in 0..expected.height {
+            for x in 0..expected.width {
+                let e = expected.pixel_at(x, y);
+                let q = |v: f64| quantize(v, max) as f64 / max;
+                assert_eq!(Color::new(q(e.0), q(e.1), q(e.2)), actual.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn computing_crc32() {
+        assert_eq!(0xae42_6082, crc32(&[b"IEND"]));
+        assert_eq!(0xcbf4_3926, crc32(&[b"1234", b"56789"]));
+    }
+
+    #[test]
+    fn png_starts_with_signature_and_header() {
+        let png = encode(&Canvas::new(300, 2), BitDepth::Eight);
+
+        assert_eq!(SIGNATURE, png[..8]);
+        assert_eq!(
+            [0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 1, 44, 0, 0, 0, 2, 8, 2, 0, 0, 0],
+            png[8..29]
+        );
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn encoding_small_image() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 0, Color::new(0.0, 0.5, 1.0));
+        let png = encode(&c, BitDepth::Eight);
+
+        // filter type 0 followed by ff 00 00 00 80 ff, compressed
+        assert_eq!(
+            [
+                0, 0, 0, 15, b'I', b'D', b'A', b'T', 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xc0,
+                0xd0, 0xf0, 0x1f, 0x00, 0x08, 0x00, 0x02, 0x7f, 0x9c, 0x45, 0x40, 0x4e
+            ],
+            png[33..60]
+        );
+    }
+    #[test]
+    fn eight_bit_png_round_trips() {
+        let canvas = sample_canvas(37, 23);
+        let decoded = decode(&encode(&canvas, BitDepth::Eight)).unwrap();
+
+        assert_quantized_equal(&canvas, &decoded, 255.0);
+    }
+
+    #[test]
+    fn sixteen_bit_png_round_trips() {
+        let canvas = sample_canvas(19, 11);
+        let png = encode(&canvas, BitDepth::Sixteen);
+
+        assert_eq!(16, png[24]);
+        assert_quantized_equal(&canvas, &decode(&png).unwrap(), 65535.0);
+    }
+
+    #[test]
+    fn every_filter_type_is_used() {
+        let canvas = sample_canvas(64, 64);
+        let png = encode(&canvas, BitDepth::Eight);
+        let idat = &png[33 + 8..png.len() - 12 - 4];
+        let data = deflate::zlib_decompress(idat).unwrap();
+
+        let filters: Vec<u8> = data.chunks(64 * 3 + 1).map(|row| row[0]).collect();
+        assert!(filters.iter().all(|&f| f < 5));
+        assert!(filters.iter().any(|&f| f != filters[0]));
+    }
+
+    #[test]
+    fn decoding_rgba_image() {
+        // 2x2 RGBA image made with Python's zlib, with ancillary tEXt chunk
+        // and the second row filtered with "up"
+        let png = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00,
+            0x00, 0x72, 0xb6, 0x0d, 0x24, 0x00, 0x00, 0x00, 0x0a, 0x74, 0x45, 0x58, 0x74, 0x43,
+            0x6f, 0x6d, 0x6d, 0x65, 0x6e, 0x74, 0x00, 0x68, 0x69, 0xa2, 0xa2, 0x58, 0x66, 0x00,
+            0x00, 0x00, 0x15, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xf8, 0xcf, 0xc0, 0xf0,
+            0x1f, 0x08, 0x1b, 0x98, 0xc0, 0x34, 0x23, 0x23, 0x03, 0x00, 0x39, 0xf4, 0x05, 0x80,
+            0xad, 0x64, 0xbd, 0x66, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42,
+            0x60, 0x82,
+        ];
+        let canvas = decode(&png).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.0), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), canvas.pixel_at(1, 0));
+        assert_eq!(Color::new(1.0, 0.0, 1.0), canvas.pixel_at(0, 1));
+        assert_eq!(Color::new(1.0, 0.0, 1.0 / 255.0), canvas.pixel_at(1, 1));
+    }
+
+    #[test]
+    fn decoding_two_bit_palette_image() {
+        let png = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x00, 0x00,
+            0x00, 0x84, 0x52, 0xe7, 0x5e, 0x00, 0x00, 0x00, 0x0c, 0x50, 0x4c, 0x54, 0x45, 0x00,
+            0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x9b, 0xc0, 0x13,
+            0xdc, 0x00, 0x00, 0x00, 0x0a, 0x74, 0x45, 0x58, 0x74, 0x43, 0x6f, 0x6d, 0x6d, 0x65,
+            0x6e, 0x74, 0x00, 0x68, 0x69, 0xa2, 0xa2, 0x58, 0x66, 0x00, 0x00, 0x00, 0x0a, 0x49,
+            0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x90, 0x06, 0x00, 0x00, 0x1d, 0x00, 0x1c, 0x23,
+            0x7c, 0x8f, 0xac, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60,
+            0x82,
+        ];
+        let canvas = decode(&png).unwrap();
+
+        assert_eq!(Color::black(), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), canvas.pixel_at(1, 0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), canvas.pixel_at(2, 0));
+        assert_eq!(Color::new(0.0, 0.0, 1.0), canvas.pixel_at(3, 0));
+    }
+
+    #[test]
+    fn malformed_png_is_rejected_with_offset() {
+        let mut png = encode(&sample_canvas(4, 4), BitDepth::Eight);
+
+        assert_eq!(
+            PngError {
+                offset: 0,
+                kind: PngErrorKind::NotPng
+            },
+            decode(b"P6\n1 1\n255\n").unwrap_err()
+        );
+        assert_eq!(
+            PngError {
+                offset: 33,
+                kind: PngErrorKind::UnexpectedEnd
+            },
+            decode(&png[..40]).unwrap_err()
+        );
+
+        // corrupt the first byte of the image data
+        png[41] ^= 0xff;
+        assert_eq!(
+            PngError {
+                offset: 33,
+                kind: PngErrorKind::ChecksumMismatch("IDAT".to_string())
+            },
+            decode(&png).unwrap_err()
+        );
+        assert_eq!(
+            "byte 33: bad checksum in IDAT chunk",
+            decode(&png).unwrap_err().to_string()
+        );
+    }
+
+    fn png_with_header(width: u32, height: u32, bit_depth: u8, color_type: u8) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(
+            &mut png,
+            b"IDAT",
+            &deflate::zlib_compress(&[0; 16], Compression::Stored),
+        );
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn huge_size_is_rejected_before_allocating() {
+        assert_eq!(
+            PngError {
+                offset: 8,
+                kind: PngErrorKind::InvalidHeader
+            },
+            decode(&png_with_header(u32::MAX, u32::MAX, 16, RGB_ALPHA)).unwrap_err()
+        );
+        let max = i32::MAX as u32;
+        assert_eq!(
+            PngError {
+                offset: 33,
+                kind: PngErrorKind::TooLarge
+            },
+            decode(&png_with_header(max, max, 16, RGB_ALPHA)).unwrap_err()
+        );
+        assert_eq!(
+            PngErrorKind::MissingImageData,
+            decode(&png_with_header(max, 1, 1, GRAYSCALE))
+                .unwrap_err()
+                .kind
+        );
+    }
+}
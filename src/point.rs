@@ -0,0 +1,133 @@
+use std::convert::TryFrom;
+use std::ops::{Add, Sub};
+
+use crate::tuple::{Tuple, TupleKind, WrongKindError};
+use crate::vector::Vector;
+use crate::EPSILON;
+
+/// Position in space (a tuple with w = 1)
+#[derive(Copy, Clone, Debug)]
+pub struct Point(pub f64, pub f64, pub f64);
+
+impl Point {
+    pub fn new(x: f64, y: f64, z: f64) -> Point {
+        Point(x, y, z)
+    }
+
+    pub fn origin() -> Point {
+        Point(0.0, 0.0, 0.0)
+    }
+}
+
+impl PartialEq for Point {
+    fn eq(&self, other: &Point) -> bool {
+        (self.0 - other.0).abs() < EPSILON
+            && (self.1 - other.1).abs() < EPSILON
+            && (self.2 - other.2).abs() < EPSILON
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, v: Vector) -> Point {
+        Point(self.0 + v.0, self.1 + v.1, self.2 + v.2)
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Point;
+
+    fn sub(self, v: Vector) -> Point {
+        Point(self.0 - v.0, self.1 - v.1, self.2 - v.2)
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Point) -> Vector {
+        Vector(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl From<Point> for Tuple {
+    fn from(p: Point) -> Tuple {
+        Tuple::point(p.0, p.1, p.2)
+    }
+}
+
+/// Fails unless `w` is 1, so that a vector cannot pass for a point
+impl TryFrom<Tuple> for Point {
+    type Error = WrongKindError;
+
+    fn try_from(t: Tuple) -> Result<Point, WrongKindError> {
+        if (t.3 - TupleKind::Point.w()).abs() < EPSILON {
+            Ok(Point(t.0, t.1, t.2))
+        } else {
+            Err(WrongKindError {
+                expected: TupleKind::Point,
+                found: t.3,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creating_point() {
+        let p = Point::new(4.3, -4.2, 3.1);
+
+        assert_eq!(4.3, p.0);
+        assert_eq!(-4.2, p.1);
+        assert_eq!(3.1, p.2);
+    }
+
+    #[test]
+    fn subtracting_two_points() {
+        let p1 = Point::new(3.0, 2.0, 1.0);
+        let p2 = Point::new(5.0, 6.0, 7.0);
+
+        assert_eq!(Vector::new(-2.0, -4.0, -6.0), p1 - p2);
+    }
+
+    #[test]
+    fn adding_and_subtracting_vector() {
+        let p = Point::new(3.0, 2.0, 1.0);
+        let v = Vector::new(5.0, 6.0, 7.0);
+
+        assert_eq!(Point::new(8.0, 8.0, 8.0), p + v);
+        assert_eq!(Point::new(-2.0, -4.0, -6.0), p - v);
+    }
+
+    #[test]
+    fn converting_point_to_and_from_tuple() {
+        let t: Tuple = Point::new(1.0, 2.0, 3.0).into();
+
+        assert_eq!(Tuple::point(1.0, 2.0, 3.0), t);
+        assert_eq!(Ok(Point::new(1.0, 2.0, 3.0)), Point::try_from(t));
+    }
+
+    #[test]
+    fn converting_vector_tuple_to_point_fails() {
+        let err = Point::try_from(Tuple::vector(1.0, 2.0, 3.0)).unwrap_err();
+
+        assert_eq!(
+            WrongKindError {
+                expected: TupleKind::Point,
+                found: 0.0
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn wrong_kind_error_names_expected_kind() {
+        let err = Point::try_from(Tuple(1.0, 2.0, 3.0, 0.5)).unwrap_err();
+
+        assert_eq!("a point needs w = 1, found 0.5", err.to_string());
+    }
+}
@@ -0,0 +1,329 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+
+#[derive(Debug, PartialEq)]
+pub struct PpmError {
+    /// Byte offset of the problem in the file
+    pub offset: usize,
+    /// Line of the problem, counting from 1
+    pub line: usize,
+    pub kind: PpmErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PpmErrorKind {
+    NotPpm,
+    UnexpectedEnd,
+    InvalidNumber(String),
+    InvalidSize,
+    TooLarge,
+    InvalidMaxval(u32),
+    ValueTooLarge { value: u32, maxval: u32 },
+}
+
+impl fmt::Display for PpmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} (byte {}): ", self.line, self.offset)?;
+        match &self.kind {
+            PpmErrorKind::NotPpm => write!(f, "not a P3 or P6 PPM file"),
+            PpmErrorKind::UnexpectedEnd => write!(f, "file ends unexpectedly"),
+            PpmErrorKind::InvalidNumber(s) => write!(f, "invalid number '{}'", s),
+            PpmErrorKind::InvalidSize => write!(f, "width and height must not be zero"),
+            PpmErrorKind::TooLarge => write!(f, "image is too large"),
+            PpmErrorKind::InvalidMaxval(v) => {
+                write!(f, "maximum value {} is not between 1 and 65535", v)
+            }
+            PpmErrorKind::ValueTooLarge { value, maxval } => {
+                write!(f, "value {} is larger than maximum value {}", value, maxval)
+            }
+        }
+    }
+}
+
+impl Error for PpmError {}
+
+/// Parse a plain (P3) or binary (P6) PPM image with any maximum value.
+/// Only the first image of a file is read.
+pub fn parse(data: &[u8]) -> Result<Canvas, PpmError> {
+    let mut input = Input { data, pos: 0 };
+    let binary = match input.token() {
+        Some((_, b"P3")) => false,
+        Some((_, b"P6")) => true,
+        _ => return Err(input.error(0, PpmErrorKind::NotPpm)),
+    };
+    let (width_at, width) = input.number()?;
+    let (_, height) = input.number()?;
+    if width == 0 || height == 0 {
+        return Err(input.error(width_at, PpmErrorKind::InvalidSize));
+    }
+    let (maxval_at, maxval) = input.number()?;
+    if maxval == 0 || maxval > 65535 {
+        return Err(input.error(maxval_at, PpmErrorKind::InvalidMaxval(maxval)));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let samples = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(3))
+        .ok_or_else(|| input.error(width_at, PpmErrorKind::TooLarge))?;
+
+    if binary {
+        // a single whitespace character separates the header from the data
+        if input
+            .data
+            .get(input.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            input.pos += 1;
+        }
+    }
+    // check that the data can be there before allocating the canvas: binary
+    // samples take one or two bytes, plain ones a digit and a separator
+    let needed = if binary {
+        samples.checked_mul(if maxval > 255 { 2 } else { 1 })
+    } else {
+        samples.checked_mul(2).map(|n| n - 1)
+    };
+    if needed.is_none_or(|n| n > data.len() - input.pos) {
+        return Err(input.error(data.len(), PpmErrorKind::UnexpectedEnd));
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let sample = |input: &mut Input| -> Result<f64, PpmError> {
+        let (at, value) = if binary {
+            input.binary_sample(maxval > 255)?
+        } else {
+            input.number()?
+        };
+        if value > maxval {
+            return Err(input.error(at, PpmErrorKind::ValueTooLarge { value, maxval }));
+        }
+        Ok(value as f64 / maxval as f64)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = (
+                sample(&mut input)?,
+                sample(&mut input)?,
+                sample(&mut input)?,
+            );
+            canvas.write_pixel(x, y, Color::new(r, g, b));
+        }
+    }
+    Ok(canvas)
+}
+
+struct Input<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    fn error(&self, offset: usize, kind: PpmErrorKind) -> PpmError {
+        let line = 1 + self.data[..offset.min(self.data.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        PpmError { offset, line, kind }
+    }
+
+    /// Skip whitespace and comments, which run from `#` to the end of the line
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if b == b'#' {
+                while self.data.get(self.pos).is_some_and(|&b| b != b'\n') {
+                    self.pos += 1;
+                }
+            } else if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Next whitespace separated token and its offset
+    fn token(&mut self) -> Option<(usize, &'a [u8])> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|&b| !b.is_ascii_whitespace() && b != b'#')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            None
+        } else {
+            Some((start, &self.data[start..self.pos]))
+        }
+    }
+
+    fn number(&mut self) -> Result<(usize, u32), PpmError> {
+        let (at, token) = self
+            .token()
+            .ok_or_else(|| self.error(self.data.len(), PpmErrorKind::UnexpectedEnd))?;
+        let text = String::from_utf8_lossy(token);
+        match text.parse() {
+            Ok(n) => Ok((at, n)),
+            Err(_) => Err(self.error(at, PpmErrorKind::InvalidNumber(text.into_owned()))),
+        }
+    }
+
+    /// One or two byte big-endian sample of binary pixel data
+    fn binary_sample(&mut self, wide: bool) -> Result<(usize, u32), PpmError> {
+        let at = self.pos;
+        let len = if wide { 2 } else { 1 };
+        let bytes = self
+            .data
+            .get(at..at + len)
+            .ok_or_else(|| self.error(self.data.len(), PpmErrorKind::UnexpectedEnd))?;
+        self.pos += len;
+        Ok((at, bytes.iter().fold(0, |v, &b| v << 8 | b as u32)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_plain_ppm_with_comments() {
+        let ppm = b"P3
+# a comment
+2 1 # another one
+15
+15 0 0   0 15 #
+7
+";
+        let canvas = parse(ppm).unwrap();
+
+        assert_eq!(2, canvas.width);
+        assert_eq!(1, canvas.height);
+        assert_eq!(Color::new(1.0, 0.0, 0.0), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 1.0, 7.0 / 15.0), canvas.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn parsing_binary_ppm() {
+        let mut ppm = b"P6\n# comment\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 51, 0, 10, 255]);
+        let canvas = parse(&ppm).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.2), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 10.0 / 255.0, 1.0), canvas.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn parsing_binary_ppm_with_two_byte_samples() {
+        let mut ppm = b"P6 1 1 1000\n".to_vec();
+        ppm.extend_from_slice(&[0x03, 0xe8, 0x01, 0xf4, 0x00, 0x00]);
+        let canvas = parse(&ppm).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), canvas.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn binary_data_may_start_with_whitespace_byte() {
+        let mut ppm = b"P6 1 1 255\n".to_vec();
+        ppm.extend_from_slice(b" \n#");
+        let canvas = parse(&ppm).unwrap();
+
+        assert_eq!(
+            Color::new(32.0 / 255.0, 10.0 / 255.0, 35.0 / 255.0),
+            canvas.pixel_at(0, 0)
+        );
+    }
+
+    #[test]
+    fn unknown_format_is_rejected() {
+        assert_eq!(
+            PpmErrorKind::NotPpm,
+            parse(b"P5 1 1 255 0").unwrap_err().kind
+        );
+        assert_eq!(PpmErrorKind::NotPpm, parse(b"").unwrap_err().kind);
+    }
+
+    #[test]
+    fn errors_have_position_of_offending_value() {
+        let err = parse(b"P3\n1 1\n255\n0 x 0\n").unwrap_err();
+
+        assert_eq!(
+            PpmError {
+                offset: 13,
+                line: 4,
+                kind: PpmErrorKind::InvalidNumber("x".to_string())
+            },
+            err
+        );
+        assert_eq!("line 4 (byte 13): invalid number 'x'", err.to_string());
+    }
+
+    #[test]
+    fn values_above_maxval_are_rejected() {
+        let err = parse(b"P3 1 1 100\n0 101 0").unwrap_err();
+
+        assert_eq!(
+            PpmError {
+                offset: 13,
+                line: 2,
+                kind: PpmErrorKind::ValueTooLarge {
+                    value: 101,
+                    maxval: 100
+                }
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn invalid_header_values_are_rejected() {
+        assert_eq!(
+            PpmErrorKind::InvalidSize,
+            parse(b"P3 0 1 255").unwrap_err().kind
+        );
+        assert_eq!(
+            PpmErrorKind::InvalidMaxval(70000),
+            parse(b"P3 1 1 70000").unwrap_err().kind
+        );
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let err = parse(b"P6 2 1 255\n\x01\x02\x03\x04").unwrap_err();
+
+        assert_eq!(
+            PpmError {
+                offset: 15,
+                line: 2,
+                kind: PpmErrorKind::UnexpectedEnd
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn huge_size_is_rejected_before_allocating() {
+        assert_eq!(
+            PpmErrorKind::TooLarge,
+            parse(b"P6 4000000000 4000000000 255\n").unwrap_err().kind
+        );
+        assert_eq!(
+            PpmError {
+                offset: 21,
+                line: 2,
+                kind: PpmErrorKind::UnexpectedEnd
+            },
+            parse(b"P3 100000 100000 255\n").unwrap_err()
+        );
+        assert_eq!(
+            PpmErrorKind::UnexpectedEnd,
+            parse(b"P6 100000 100000 65535\n\0\0").unwrap_err().kind
+        );
+    }
+}
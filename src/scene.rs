@@ -0,0 +1,1375 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cone::Cone;
+use crate::csg::{Csg, Operation};
+use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::group::Group;
+use crate::lights::PointLight;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::pattern::{CheckersPattern, GradientPattern, Pattern, RingPattern, StripePattern};
+use crate::plane::Plane;
+use crate::point::Point;
+use crate::shape::Shape;
+use crate::sphere::Sphere;
+use crate::transformation;
+use crate::triangle::Triangle;
+use crate::vector::Vector;
+use crate::world::World;
+
+/// Camera and world read from a scene file
+#[derive(Debug)]
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SceneError {
+    pub line: usize,
+    pub kind: SceneErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SceneErrorKind {
+    Syntax(String),
+    UnknownKey(String),
+    MissingKey(&'static str),
+    Undefined(String),
+    Recursive(String),
+    InvalidValue {
+        expected: &'static str,
+        found: String,
+    },
+    WrongArgumentCount {
+        expected: usize,
+        found: usize,
+    },
+    MissingCamera,
+    SingularTransform,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            SceneErrorKind::Syntax(s) => write!(f, "{}", s),
+            SceneErrorKind::UnknownKey(k) => write!(f, "unknown key '{}'", k),
+            SceneErrorKind::MissingKey(k) => write!(f, "missing key '{}'", k),
+            SceneErrorKind::Undefined(name) => write!(f, "'{}' is not defined", name),
+            SceneErrorKind::Recursive(name) => {
+                write!(f, "'{}' is defined in terms of itself", name)
+            }
+            SceneErrorKind::InvalidValue { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            SceneErrorKind::WrongArgumentCount { expected, found } => {
+                write!(f, "expected {} arguments, found {}", expected, found)
+            }
+            SceneErrorKind::MissingCamera => write!(f, "scene has no camera"),
+            SceneErrorKind::SingularTransform => write!(f, "transformation cannot be inverted"),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+fn error(line: usize, kind: SceneErrorKind) -> SceneError {
+    SceneError { line, kind }
+}
+
+/// Parse a scene in the YAML-like format of the book's bonus chapters.
+///
+/// The file is a list of commands. `add: camera` and `add: light` set up
+/// the view and the lights, `add: <shape>` puts a sphere, plane, cube,
+/// cylinder, cone, triangle, group or csg into the world, and
+/// `define: <name>` names a material, transform or shape for later use,
+/// optionally `extend`ing an earlier definition:
+///
+/// ```yaml
+/// - add: camera
+///   width: 100
+///   height: 50
+///   field-of-view: 1.0472
+///   from: [0, 1.5, -5]
+///   to: [0, 1, 0]
+///   up: [0, 1, 0]
+///
+/// - add: light
+///   at: [-10, 10, -10]
+///   intensity: [1, 1, 1]
+///
+/// - define: red
+///   value:
+///     color: [1, 0, 0]
+///     specular: 0.2
+///
+/// - define: shiny-red
+///   extend: red
+///   value:
+///     reflective: 0.5
+///
+/// - add: sphere
+///   material: shiny-red
+///   transform:
+///     - [scale, 0.5, 0.5, 0.5]
+///     - [translate, 0, 1, 0]
+/// ```
+///
+/// Transforms are applied in the order they are listed. The objects of the
+/// loaded world are already divided into a bounding volume hierarchy.
+pub fn parse_scene(input: &str) -> Result<Scene, SceneError> {
+    let document = parse_document(input)?;
+    let mut loader = Loader {
+        definitions: HashMap::new(),
+        camera: None,
+        world: World::new(),
+    };
+    for command in document.as_list("a list of commands")? {
+        loader.command(command)?;
+    }
+
+    loader.world.build_bvh();
+    match loader.camera {
+        Some(camera) => Ok(Scene {
+            camera,
+            world: loader.world,
+        }),
+        None => Err(error(
+            input.lines().count().max(1),
+            SceneErrorKind::MissingCamera,
+        )),
+    }
+}
+
+/// Value in a scene document together with the line it starts on
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<Entry>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    key: String,
+    line: usize,
+    value: Node,
+}
+
+impl Node {
+    fn invalid(&self, expected: &'static str) -> SceneError {
+        let found = match &self.value {
+            Value::Scalar(s) => format!("'{}'", s),
+            Value::List(_) => "a list".to_string(),
+            Value::Map(_) => "a mapping".to_string(),
+        };
+        error(self.line, SceneErrorKind::InvalidValue { expected, found })
+    }
+
+    fn as_str(&self, expected: &'static str) -> Result<&str, SceneError> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => Err(self.invalid(expected)),
+        }
+    }
+
+    fn as_list(&self, expected: &'static str) -> Result<&[Node], SceneError> {
+        match &self.value {
+            Value::List(items) => Ok(items),
+            _ => Err(self.invalid(expected)),
+        }
+    }
+
+    fn as_map(&self, expected: &'static str) -> Result<&[Entry], SceneError> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => Err(self.invalid(expected)),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64, SceneError> {
+        self.as_str("a number")?
+            .parse()
+            .map_err(|_| self.invalid("a number"))
+    }
+
+    fn as_usize(&self) -> Result<usize, SceneError> {
+        self.as_str("a positive integer")?
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| self.invalid("a positive integer"))
+    }
+
+    fn as_bool(&self) -> Result<bool, SceneError> {
+        match self.as_str("true or false")? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(self.invalid("true or false")),
+        }
+    }
+
+    fn as_triple(&self) -> Result<[f64; 3], SceneError> {
+        const EXPECTED: &str = "a list of three numbers";
+        match self.as_list(EXPECTED)? {
+            [x, y, z] => Ok([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+            _ => Err(self.invalid(EXPECTED)),
+        }
+    }
+
+    fn as_point(&self) -> Result<Point, SceneError> {
+        let [x, y, z] = self.as_triple()?;
+        Ok(Point::new(x, y, z))
+    }
+
+    fn as_vector(&self) -> Result<Vector, SceneError> {
+        let [x, y, z] = self.as_triple()?;
+        Ok(Vector::new(x, y, z))
+    }
+
+    fn as_color(&self) -> Result<Color, SceneError> {
+        let [r, g, b] = self.as_triple()?;
+        Ok(Color::new(r, g, b))
+    }
+}
+
+/// Last value given for `key`
+fn get<'a>(entries: &'a [Entry], key: &str) -> Option<&'a Node> {
+    entries
+        .iter()
+        .rev()
+        .find(|e| e.key == key)
+        .map(|e| &e.value)
+}
+
+fn require<'a>(
+    entries: &'a [Entry],
+    key: &'static str,
+    line: usize,
+) -> Result<&'a Node, SceneError> {
+    get(entries, key).ok_or_else(|| error(line, SceneErrorKind::MissingKey(key)))
+}
+
+fn check_keys(entries: &[Entry], allowed: &[&str]) -> Result<(), SceneError> {
+    match entries.iter().find(|e| !allowed.contains(&e.key.as_str())) {
+        Some(e) => Err(error(e.line, SceneErrorKind::UnknownKey(e.key.clone()))),
+        None => Ok(()),
+    }
+}
+
+/// Entries of `base` that `overrides` does not replace, followed by `overrides`
+fn merge(base: &[Entry], overrides: &[Entry]) -> Vec<Entry> {
+    base.iter()
+        .filter(|b| overrides.iter().all(|o| o.key != b.key))
+        .chain(overrides)
+        .cloned()
+        .collect()
+}
+
+const CAMERA_KEYS: &[&str] = &[
+    "add",
+    "width",
+    "height",
+    "field-of-view",
+    "from",
+    "to",
+    "up",
+];
+const LIGHT_KEYS: &[&str] = &["add", "at", "intensity"];
+const DEFINE_KEYS: &[&str] = &["define", "extend", "value"];
+const SHAPE_KEYS: &[&str] = &["add", "material", "transform"];
+const CYLINDER_KEYS: &[&str] = &["add", "material", "transform", "min", "max", "closed"];
+const TRIANGLE_KEYS: &[&str] = &["add", "material", "transform", "p1", "p2", "p3"];
+const GROUP_KEYS: &[&str] = &["add", "material", "transform", "children"];
+const CSG_KEYS: &[&str] = &["add", "material", "transform", "operation", "left", "right"];
+const MATERIAL_KEYS: &[&str] = &[
+    "color",
+    "pattern",
+    "ambient",
+    "diffuse",
+    "specular",
+    "shininess",
+    "reflective",
+    "transparency",
+    "refractive-index",
+];
+const PATTERN_KEYS: &[&str] = &["type", "colors", "transform"];
+const SHAPES: &[&str] = &[
+    "sphere", "plane", "cube", "cylinder", "cone", "triangle", "group", "csg",
+];
+
+struct Loader {
+    definitions: HashMap<String, Node>,
+    camera: Option<Camera>,
+    world: World,
+}
+
+impl Loader {
+    fn command(&mut self, node: &Node) -> Result<(), SceneError> {
+        let entries = node.as_map("a command")?;
+        if let Some(name) = get(entries, "define") {
+            return self.define(name.as_str("a name")?, entries, node.line);
+        }
+
+        let add = require(entries, "add", node.line)?;
+        match add.as_str("an object to add")? {
+            "camera" => self.camera = Some(camera(entries, node.line)?),
+            "light" => self.world.add_light(light(entries, node.line)?),
+            _ => {
+                let shape = self.shape(node)?;
+                self.world.objects.push(shape);
+            }
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, entries: &[Entry], line: usize) -> Result<(), SceneError> {
+        check_keys(entries, DEFINE_KEYS)?;
+        let mut value = require(entries, "value", line)?.clone();
+        if let Some(base) = get(entries, "extend") {
+            let base = self.lookup(base)?.as_map("a mapping to extend")?;
+            let overrides = value.as_map("a mapping")?;
+            value.value = Value::Map(merge(base, overrides));
+        }
+        self.definitions.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// The definition `node` names, or `node` itself if it is not a name
+    fn lookup<'a>(&'a self, node: &'a Node) -> Result<&'a Node, SceneError> {
+        match &node.value {
+            Value::Scalar(name) => self
+                .definitions
+                .get(name)
+                .ok_or_else(|| error(node.line, SceneErrorKind::Undefined(name.clone()))),
+            _ => Ok(node),
+        }
+    }
+
+    /// Entries of a shape, with defined shapes replaced by their definition
+    fn shape_entries(&self, node: &Node) -> Result<Vec<Entry>, SceneError> {
+        let mut entries = node.as_map("a shape")?.to_vec();
+        let mut expanded = vec![];
+        loop {
+            let add = require(&entries, "add", node.line)?;
+            let kind = add.as_str("a shape")?;
+            if SHAPES.contains(&kind) {
+                return Ok(entries);
+            }
+            if expanded.iter().any(|name| name == kind) {
+                return Err(error(add.line, SceneErrorKind::Recursive(kind.to_string())));
+            }
+            let definition = match self.definitions.get(kind) {
+                Some(definition) => definition.as_map("a shape")?,
+                None => return Err(add.invalid("a shape")),
+            };
+            expanded.push(kind.to_string());
+            let overrides: Vec<Entry> = entries.into_iter().filter(|e| e.key != "add").collect();
+            entries = merge(definition, &overrides);
+        }
+    }
+
+    fn shape(&self, node: &Node) -> Result<Box<dyn Shape>, SceneError> {
+        let entries = self.shape_entries(node)?;
+        let entries = entries.as_slice();
+        let line = node.line;
+        let mut shape: Box<dyn Shape> = match require(entries, "add", line)?.as_str("a shape")? {
+            "sphere" => {
+                check_keys(entries, SHAPE_KEYS)?;
+                Box::new(Sphere::new())
+            }
+            "plane" => {
+                check_keys(entries, SHAPE_KEYS)?;
+                Box::new(Plane::new())
+            }
+            "cube" => {
+                check_keys(entries, SHAPE_KEYS)?;
+                Box::new(Cube::new())
+            }
+            "cylinder" => {
+                check_keys(entries, CYLINDER_KEYS)?;
+                let mut c = Cylinder::new();
+                let (minimum, maximum, closed) = truncation(entries)?;
+                c.minimum = minimum.unwrap_or(c.minimum);
+                c.maximum = maximum.unwrap_or(c.maximum);
+                c.closed = closed.unwrap_or(c.closed);
+                Box::new(c)
+            }
+            "cone" => {
+                check_keys(entries, CYLINDER_KEYS)?;
+                let mut c = Cone::new();
+                let (minimum, maximum, closed) = truncation(entries)?;
+                c.minimum = minimum.unwrap_or(c.minimum);
+                c.maximum = maximum.unwrap_or(c.maximum);
+                c.closed = closed.unwrap_or(c.closed);
+                Box::new(c)
+            }
+            "triangle" => {
+                check_keys(entries, TRIANGLE_KEYS)?;
+                Box::new(Triangle::new(
+                    require(entries, "p1", line)?.as_point()?,
+                    require(entries, "p2", line)?.as_point()?,
+                    require(entries, "p3", line)?.as_point()?,
+                ))
+            }
+            "group" => {
+                check_keys(entries, GROUP_KEYS)?;
+                let mut group = Group::new();
+                if let Some(children) = get(entries, "children") {
+                    for child in children.as_list("a list of shapes")? {
+                        group.add_boxed_child(self.shape(child)?);
+                    }
+                }
+                Box::new(group)
+            }
+            "csg" => {
+                check_keys(entries, CSG_KEYS)?;
+                let operation = require(entries, "operation", line)?;
+                let operation = match operation.as_str("a csg operation")? {
+                    "union" => Operation::Union,
+                    "intersection" => Operation::Intersection,
+                    "difference" => Operation::Difference,
+                    _ => return Err(operation.invalid("union, intersection or difference")),
+                };
+                let left = self.shape(require(entries, "left", line)?)?;
+                let right = self.shape(require(entries, "right", line)?)?;
+                Box::new(Csg::from_boxed(operation, left, right))
+            }
+            _ => unreachable!("shape_entries only returns known shapes"),
+        };
+
+        if let Some(transform) = get(entries, "transform") {
+            shape.set_transform(self.transform(transform)?);
+        }
+        if let Some(material) = get(entries, "material") {
+            *shape.material_mut() = self.material(material)?;
+        }
+        Ok(shape)
+    }
+
+    fn material(&self, node: &Node) -> Result<Material, SceneError> {
+        let entries = self.lookup(node)?.as_map("a material")?;
+        check_keys(entries, MATERIAL_KEYS)?;
+        let mut m = Material::new();
+        for e in entries {
+            let v = &e.value;
+            match e.key.as_str() {
+                "color" => m.color = v.as_color()?,
+                "pattern" => m.pattern = Some(self.pattern(v)?),
+                "ambient" => m.ambient = v.as_f64()?,
+                "diffuse" => m.diffuse = v.as_f64()?,
+                "specular" => m.specular = v.as_f64()?,
+                "shininess" => m.shininess = v.as_f64()?,
+                "reflective" => m.reflective = v.as_f64()?,
+                "transparency" => m.transparency = v.as_f64()?,
+                "refractive-index" => m.refractive_index = v.as_f64()?,
+                _ => unreachable!("keys are checked above"),
+            }
+        }
+        Ok(m)
+    }
+
+    fn pattern(&self, node: &Node) -> Result<Arc<dyn Pattern>, SceneError> {
+        let entries = self.lookup(node)?.as_map("a pattern")?;
+        check_keys(entries, PATTERN_KEYS)?;
+        let colors = require(entries, "colors", node.line)?;
+        let (a, b) = match colors.as_list("a list of two colors")? {
+            [a, b] => (a.as_color()?, b.as_color()?),
+            _ => return Err(colors.invalid("a list of two colors")),
+        };
+        let transform = match get(entries, "transform") {
+            Some(t) => self.transform(t)?,
+            None => Matrix4::identity(),
+        };
+
+        let kind = require(entries, "type", node.line)?;
+        let mut pattern: Box<dyn Pattern> = match kind.as_str("a pattern type")? {
+            "stripes" => Box::new(StripePattern::new(a, b)),
+            "gradient" => Box::new(GradientPattern::new(a, b)),
+            "rings" => Box::new(RingPattern::new(a, b)),
+            "checkers" => Box::new(CheckersPattern::new(a, b)),
+            _ => return Err(kind.invalid("stripes, gradient, rings or checkers")),
+        };
+        pattern.set_transform(transform);
+        Ok(Arc::from(pattern))
+    }
+
+    /// Combine a list of transformations, and names of defined lists of
+    /// them, so that they are applied in the listed order. The result must
+    /// be invertible, as it is not possible to render with it otherwise.
+    fn transform(&self, node: &Node) -> Result<Matrix4, SceneError> {
+        let mut expanded = vec![];
+        let m = self.transform_steps(node, &mut expanded)?;
+        if !m.invertible() {
+            return Err(error(node.line, SceneErrorKind::SingularTransform));
+        }
+        Ok(m)
+    }
+
+    fn transform_steps(
+        &self,
+        node: &Node,
+        expanded: &mut Vec<String>,
+    ) -> Result<Matrix4, SceneError> {
+        let mut m = Matrix4::identity();
+        for step in self.lookup(node)?.as_list("a list of transformations")? {
+            let t = match &step.value {
+                Value::Scalar(name) => {
+                    if expanded.contains(name) {
+                        return Err(error(step.line, SceneErrorKind::Recursive(name.clone())));
+                    }
+                    expanded.push(name.clone());
+                    let t = self.transform_steps(step, expanded)?;
+                    expanded.pop();
+                    t
+                }
+                _ => transformation_step(step)?,
+            };
+            m = t * m;
+        }
+        Ok(m)
+    }
+}
+
+fn camera(entries: &[Entry], line: usize) -> Result<Camera, SceneError> {
+    check_keys(entries, CAMERA_KEYS)?;
+    let mut camera = Camera::new(
+        require(entries, "width", line)?.as_usize()?,
+        require(entries, "height", line)?.as_usize()?,
+        require(entries, "field-of-view", line)?.as_f64()?,
+    );
+    let from = require(entries, "from", line)?.as_point()?;
+    let to = require(entries, "to", line)?;
+    let up = require(entries, "up", line)?;
+    let view = transformation::view_transform(from, to.as_point()?, up.as_vector()?);
+    if !view.invertible() {
+        // either `to` is at `from` or `up` points along the line of sight
+        let culprit = if to.as_point()? == from { to } else { up };
+        return Err(error(culprit.line, SceneErrorKind::SingularTransform));
+    }
+    camera.set_transform(view);
+    Ok(camera)
+}
+
+fn light(entries: &[Entry], line: usize) -> Result<PointLight, SceneError> {
+    check_keys(entries, LIGHT_KEYS)?;
+    Ok(PointLight::new(
+        require(entries, "at", line)?.as_point()?,
+        require(entries, "intensity", line)?.as_color()?,
+    ))
+}
+
+type Truncation = (Option<f64>, Option<f64>, Option<bool>);
+
+/// `min`, `max` and `closed` of a cylinder or cone, if given
+fn truncation(entries: &[Entry]) -> Result<Truncation, SceneError> {
+    Ok((
+        get(entries, "min").map(Node::as_f64).transpose()?,
+        get(entries, "max").map(Node::as_f64).transpose()?,
+        get(entries, "closed").map(Node::as_bool).transpose()?,
+    ))
+}
+
+/// Single transformation such as `[translate, 1, 2, 3]`
+fn transformation_step(node: &Node) -> Result<Matrix4, SceneError> {
+    const EXPECTED: &str = "a transformation";
+    let (name, args) = match node.as_list(EXPECTED)? {
+        [name, args @ ..] => (name.as_str(EXPECTED)?, args),
+        [] => return Err(node.invalid(EXPECTED)),
+    };
+    let args = args
+        .iter()
+        .map(Node::as_f64)
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = match name {
+        "translate" | "scale" => 3,
+        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+        "shear" => 6,
+        _ => return Err(node.invalid(EXPECTED)),
+    };
+    if args.len() != expected {
+        return Err(error(
+            node.line,
+            SceneErrorKind::WrongArgumentCount {
+                expected,
+                found: args.len(),
+            },
+        ));
+    }
+
+    Ok(match name {
+        "translate" => transformation::translation(args[0], args[1], args[2]),
+        "scale" => transformation::scaling(args[0], args[1], args[2]),
+        "rotate-x" => transformation::rotation_x(args[0]),
+        "rotate-y" => transformation::rotation_y(args[0]),
+        "rotate-z" => transformation::rotation_z(args[0]),
+        _ => transformation::shearing(args[0], args[1], args[2], args[3], args[4], args[5]),
+    })
+}
+
+/// Non-blank line with its comment removed
+#[derive(Clone, Copy, Debug)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+/// Parse the subset of YAML used by scene files: block lists and mappings
+/// nested by indentation, flow lists such as `[1, 2, 3]`, and scalars
+fn parse_document(input: &str) -> Result<Node, SceneError> {
+    let mut lines = vec![];
+    for (n, raw) in input.lines().enumerate() {
+        let number = n + 1;
+        let text = strip_comment(raw).trim_end();
+        let content = text.trim_start();
+        if content.is_empty() || content == "---" {
+            continue;
+        }
+        let indentation = &text[..text.len() - content.len()];
+        if indentation.contains('\t') {
+            return Err(error(
+                number,
+                SceneErrorKind::Syntax("tabs are not allowed in indentation".to_string()),
+            ));
+        }
+        lines.push(Line {
+            number,
+            indent: indentation.len(),
+            text: content,
+        });
+    }
+
+    let first = match lines.first() {
+        Some(line) => *line,
+        None => {
+            return Ok(Node {
+                line: 1,
+                value: Value::List(vec![]),
+            })
+        }
+    };
+    let mut parser = Parser { lines, pos: 0 };
+    let document = parser.block(first.indent)?;
+    match parser.lines.get(parser.pos) {
+        Some(line) => Err(error(
+            line.number,
+            SceneErrorKind::Syntax("inconsistent indentation".to_string()),
+        )),
+        None => Ok(document),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            return &line[..i];
+        }
+        prev = c;
+    }
+    line
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Split `key: value` into the key and the (possibly empty) value
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with('[') || text.starts_with('"') {
+        return None;
+    }
+    let colon = match text.find(": ") {
+        Some(i) => i,
+        None if text.ends_with(':') => text.len() - 1,
+        None => return None,
+    };
+    let key = text[..colon].trim();
+    if key.is_empty() {
+        None
+    } else {
+        Some((key, text[colon + 1..].trim()))
+    }
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse the node starting at the current line, which is at `indent`
+    fn block(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let line = self.lines[self.pos];
+        if is_item(line.text) {
+            self.list(indent)
+        } else if split_key(line.text).is_some() {
+            self.map(indent)
+        } else {
+            self.pos += 1;
+            let node = parse_flow(line.text, line.number)?;
+            self.check_dedent(indent)?;
+            Ok(node)
+        }
+    }
+
+    fn list(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let number = self.lines[self.pos].number;
+        let mut items = vec![];
+        while let Some(&line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_item(line.text) {
+                break;
+            }
+            let rest = &line.text[1..];
+            let item = rest.trim_start();
+            if item.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, line.number)?);
+            } else {
+                // parse the rest of the line as if it started a block of its own
+                let item_indent = indent + 1 + rest.len() - item.len();
+                self.lines[self.pos] = Line {
+                    number: line.number,
+                    indent: item_indent,
+                    text: item,
+                };
+                items.push(self.block(item_indent)?);
+            }
+        }
+        self.check_dedent(indent)?;
+        Ok(Node {
+            line: number,
+            value: Value::List(items),
+        })
+    }
+
+    fn map(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let number = self.lines[self.pos].number;
+        let mut entries = vec![];
+        while let Some(&line) = self.lines.get(self.pos) {
+            if line.indent != indent {
+                break;
+            }
+            let (key, rest) = split_key(line.text).ok_or_else(|| {
+                error(
+                    line.number,
+                    SceneErrorKind::Syntax("expected 'key: value'".to_string()),
+                )
+            })?;
+            self.pos += 1;
+            let value = match self.lines.get(self.pos) {
+                // a list may be at the same indentation as its key
+                Some(next) if rest.is_empty() && next.indent == indent && is_item(next.text) => {
+                    self.list(indent)?
+                }
+                _ if rest.is_empty() => self.nested(indent, line.number)?,
+                _ => parse_flow(rest, line.number)?,
+            };
+            entries.push(Entry {
+                key: key.to_string(),
+                line: line.number,
+                value,
+            });
+        }
+        self.check_dedent(indent)?;
+        Ok(Node {
+            line: number,
+            value: Value::Map(entries),
+        })
+    }
+
+    /// Block indented deeper than `indent`, or an empty value if there is none
+    fn nested(&mut self, indent: usize, number: usize) -> Result<Node, SceneError> {
+        match self.lines.get(self.pos) {
+            Some(next) if next.indent > indent => self.block(next.indent),
+            _ => Ok(Node {
+                line: number,
+                value: Value::Scalar(String::new()),
+            }),
+        }
+    }
+
+    fn check_dedent(&self, indent: usize) -> Result<(), SceneError> {
+        match self.lines.get(self.pos) {
+            Some(line) if line.indent > indent => Err(error(
+                line.number,
+                SceneErrorKind::Syntax("inconsistent indentation".to_string()),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parse a scalar or a flow list such as `[translate, 1, [2, 3]]`
+fn parse_flow(text: &str, line: usize) -> Result<Node, SceneError> {
+    let mut flow = Flow { text, pos: 0, line };
+    let node = flow.value(false)?;
+    flow.skip_whitespace();
+    match flow.peek() {
+        None => Ok(node),
+        Some(c) => Err(flow.unexpected(c)),
+    }
+}
+
+struct Flow<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Flow<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn unexpected(&self, c: char) -> SceneError {
+        error(
+            self.line,
+            SceneErrorKind::Syntax(format!("unexpected '{}'", c)),
+        )
+    }
+
+    /// Scalars inside a list end at the next `,` or `]`
+    fn value(&mut self, in_list: bool) -> Result<Node, SceneError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                self.list()
+            }
+            Some('{') => Err(error(
+                self.line,
+                SceneErrorKind::Syntax("flow mappings are not supported".to_string()),
+            )),
+            _ => {
+                let rest = &self.text[self.pos..];
+                let end = if in_list {
+                    rest.find([',', ']', '[']).unwrap_or(rest.len())
+                } else {
+                    rest.len()
+                };
+                self.pos += end;
+                let s = rest[..end].trim();
+                let s = s
+                    .strip_prefix('"')
+                    .and_then(|s| s.strip_suffix('"'))
+                    .unwrap_or(s);
+                Ok(Node {
+                    line: self.line,
+                    value: Value::Scalar(s.to_string()),
+                })
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Node, SceneError> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                break;
+            }
+            items.push(self.value(true)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(c) => return Err(self.unexpected(c)),
+                None => {
+                    return Err(error(
+                        self.line,
+                        SceneErrorKind::Syntax("unterminated '['".to_string()),
+                    ))
+                }
+            }
+        }
+        Ok(Node {
+            line: self.line,
+            value: Value::List(items),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    const CAMERA: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+";
+
+    fn scalar(line: usize, s: &str) -> Node {
+        Node {
+            line,
+            value: Value::Scalar(s.to_string()),
+        }
+    }
+
+    fn parse_error(input: &str) -> SceneError {
+        parse_scene(&format!("{}{}", CAMERA, input)).unwrap_err()
+    }
+
+    /// The only bounded object of a scene, which `build_bvh` has moved into a
+    /// group of its own
+    fn only_object(scene: &Scene) -> &dyn Shape {
+        let root = scene.world.objects[0]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap();
+        assert_eq!(1, root.children().len());
+        root.children()[0].as_ref()
+    }
+
+    #[test]
+    fn parsing_nested_lists_and_mappings() {
+        let doc = parse_document(
+            "- add: light # comment
+  at: [1, -2.5, 3]
+- list:
+  - a
+  -
+    b: [[x], []]",
+        )
+        .unwrap();
+        let commands = doc.as_list("").unwrap();
+
+        assert_eq!(2, commands.len());
+        let light = commands[0].as_map("").unwrap();
+        assert_eq!("add", light[0].key);
+        assert_eq!(scalar(1, "light"), light[0].value);
+        assert_eq!(2, light[1].line);
+        assert_eq!([1.0, -2.5, 3.0], light[1].value.as_triple().unwrap());
+
+        let list = get(commands[1].as_map("").unwrap(), "list").unwrap();
+        let items = list.as_list("").unwrap();
+        assert_eq!(scalar(4, "a"), items[0]);
+        let b = get(items[1].as_map("").unwrap(), "b").unwrap();
+        let b = b.as_list("").unwrap();
+        assert_eq!(vec![scalar(6, "x")], b[0].as_list("").unwrap());
+        assert!(b[1].as_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn inconsistent_indentation_is_an_error() {
+        let err = parse_document("- a: 1\n   b: 2").unwrap_err();
+
+        assert_eq!(2, err.line);
+    }
+
+    #[test]
+    fn unterminated_flow_list_is_an_error() {
+        let err = parse_document("- a: 1\n- b: [1, 2").unwrap_err();
+
+        assert_eq!(
+            SceneError {
+                line: 2,
+                kind: SceneErrorKind::Syntax("unterminated '['".to_string())
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn parsing_camera() {
+        let scene = parse_scene(CAMERA).unwrap();
+
+        assert_eq!(100, scene.camera.hsize());
+        assert_eq!(50, scene.camera.vsize());
+        assert_eq!(0.785, scene.camera.field_of_view());
+        assert_eq!(
+            transformation::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::origin(),
+                Vector::new(0.0, 1.0, 0.0)
+            ),
+            *scene.camera.transform()
+        );
+        assert!(scene.world.objects.is_empty());
+    }
+
+    #[test]
+    fn scene_without_camera_is_an_error() {
+        let err = parse_scene("- add: sphere\n").unwrap_err();
+
+        assert_eq!(SceneErrorKind::MissingCamera, err.kind);
+    }
+
+    #[test]
+    fn parsing_light() {
+        let scene = parse_scene(&format!(
+            "{}- add: light\n  at: [-10, 10, -10]\n  intensity: [1, 0.5, 1]",
+            CAMERA
+        ))
+        .unwrap();
+
+        assert_eq!(
+            vec![PointLight::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::new(1.0, 0.5, 1.0)
+            )],
+            scene.world.lights
+        );
+    }
+
+    #[test]
+    fn transformations_are_applied_in_listed_order() {
+        let scene = parse_scene(&format!(
+            "{}
+- add: sphere
+  transform:
+    - [rotate-x, {}]
+    - [scale, 5, 5, 5]
+    - [translate, 10, 5, 7]
+",
+            CAMERA,
+            PI / 2.0
+        ))
+        .unwrap();
+
+        let expected = transformation::translation(10.0, 5.0, 7.0)
+            * transformation::scaling(5.0, 5.0, 5.0)
+            * transformation::rotation_x(PI / 2.0);
+        assert_eq!(expected, *only_object(&scene).transform());
+    }
+
+    #[test]
+    fn defining_and_extending_materials() {
+        let scene = parse_scene(&format!(
+            "{}
+- define: white
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    reflective: 0.1
+
+- define: blue
+  extend: white
+  value:
+    color: [0, 0, 1]
+
+- add: cube
+  material: blue
+",
+            CAMERA
+        ))
+        .unwrap();
+
+        let m = only_object(&scene).material();
+        assert_eq!(Color::new(0.0, 0.0, 1.0), m.color);
+        assert_eq!(0.7, m.diffuse);
+        assert_eq!(0.1, m.reflective);
+        assert_eq!(Material::new().ambient, m.ambient);
+    }
+
+    #[test]
+    fn defined_transforms_are_expanded_in_place() {
+        let scene = parse_scene(&format!(
+            "{}
+- define: standard
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+
+- add: sphere
+  transform:
+    - standard
+    - [scale, 2, 2, 2]
+",
+            CAMERA
+        ))
+        .unwrap();
+
+        let expected = transformation::scaling(2.0, 2.0, 2.0)
+            * transformation::scaling(0.5, 0.5, 0.5)
+            * transformation::translation(1.0, -1.0, 1.0);
+        assert_eq!(expected, *only_object(&scene).transform());
+    }
+
+    #[test]
+    fn adding_defined_shape() {
+        let scene = parse_scene(&format!(
+            "{}
+- define: post
+  value:
+    add: cylinder
+    min: 0
+    max: 1
+    closed: true
+
+- add: post
+  transform:
+    - [translate, 1, 0, 0]
+",
+            CAMERA
+        ))
+        .unwrap();
+
+        let post = only_object(&scene)
+            .as_any()
+            .downcast_ref::<Cylinder>()
+            .unwrap();
+        assert_eq!(0.0, post.minimum);
+        assert_eq!(1.0, post.maximum);
+        assert!(post.closed);
+        assert_eq!(
+            transformation::translation(1.0, 0.0, 0.0),
+            *post.transform()
+        );
+    }
+
+    #[test]
+    fn parsing_groups_and_csg() {
+        let scene = parse_scene(&format!(
+            "{}
+- add: group
+  children:
+    - add: sphere
+    - add: csg
+      operation: difference
+      left:
+        add: cube
+      right:
+        add: sphere
+        transform:
+          - [scale, 0.5, 0.5, 0.5]
+",
+            CAMERA
+        ))
+        .unwrap();
+
+        let group = only_object(&scene)
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap();
+        assert_eq!(2, group.children().len());
+        let csg = group.children()[1].as_any().downcast_ref::<Csg>().unwrap();
+        assert_eq!(Operation::Difference, csg.operation);
+        assert!(csg.left().as_any().is::<Cube>());
+        assert_eq!(
+            transformation::scaling(0.5, 0.5, 0.5),
+            *csg.right().transform()
+        );
+    }
+
+    #[test]
+    fn loaded_objects_are_divided_into_bvh() {
+        let scene = parse_scene(&format!(
+            "{}- add: sphere\n- add: plane\n- add: cube\n",
+            CAMERA
+        ))
+        .unwrap();
+
+        assert_eq!(2, scene.world.objects.len());
+        assert!(scene.world.objects[0].as_any().is::<Plane>());
+        let root = scene.world.objects[1]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap();
+        assert_eq!(2, root.children().len());
+    }
+
+    #[test]
+    fn parsing_pattern() {
+        let scene = parse_scene(&format!(
+            "{}
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [1, 1, 1]
+        - [0, 0, 0]
+      transform:
+        - [scale, 0.25, 0.25, 0.25]
+",
+            CAMERA
+        ))
+        .unwrap();
+
+        let mut expected = CheckersPattern::new(Color::white(), Color::black());
+        expected.set_transform(transformation::scaling(0.25, 0.25, 0.25));
+        let pattern = scene.world.objects[0].material().pattern.as_ref().unwrap();
+        assert!(pattern.as_ref() == &expected as &dyn Pattern);
+    }
+
+    #[test]
+    fn unknown_key_is_reported_with_its_line() {
+        let err = parse_error("- add: sphere\n  colour: [1, 0, 0]\n");
+
+        assert_eq!(
+            SceneError {
+                line: 10,
+                kind: SceneErrorKind::UnknownKey("colour".to_string())
+            },
+            err
+        );
+        assert_eq!("line 10: unknown key 'colour'", err.to_string());
+    }
+
+    #[test]
+    fn invalid_value_is_reported_with_its_line() {
+        let err = parse_error("- add: sphere\n  material:\n    diffuse: lots\n");
+
+        assert_eq!(11, err.line);
+        assert_eq!("line 11: expected a number, found 'lots'", err.to_string());
+    }
+
+    #[test]
+    fn undefined_name_is_an_error() {
+        let err = parse_error("- add: sphere\n  material: chrome\n");
+
+        assert_eq!(
+            SceneError {
+                line: 10,
+                kind: SceneErrorKind::Undefined("chrome".to_string())
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn unknown_shape_is_an_error() {
+        let err = parse_error("- add: teapot\n");
+
+        assert_eq!(9, err.line);
+        assert_eq!("line 9: expected a shape, found 'teapot'", err.to_string());
+    }
+
+    #[test]
+    fn wrong_number_of_transformation_arguments_is_an_error() {
+        let err = parse_error("- add: sphere\n  transform:\n    - [scale, 2, 2]\n");
+
+        assert_eq!(
+            SceneError {
+                line: 11,
+                kind: SceneErrorKind::WrongArgumentCount {
+                    expected: 3,
+                    found: 2
+                }
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn singular_transformation_is_an_error() {
+        let err = parse_error("- add: sphere\n  transform:\n    - [scale, 1, 0, 1]\n");
+
+        assert_eq!(
+            SceneError {
+                line: 11,
+                kind: SceneErrorKind::SingularTransform
+            },
+            err
+        );
+        assert_eq!(
+            "line 11: transformation cannot be inverted",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn small_scaling_is_not_singular() {
+        let scene = parse_scene(&format!(
+            "{}- add: sphere\n  transform:\n    - [scale, 0.02, 0.02, 0.02]\n",
+            CAMERA
+        ))
+        .unwrap();
+
+        assert_eq!(
+            transformation::scaling(0.02, 0.02, 0.02),
+            *only_object(&scene).transform()
+        );
+    }
+
+    #[test]
+    fn camera_looking_at_its_own_position_is_an_error() {
+        let err = parse_scene(
+            "- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 1, 0]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            SceneError {
+                line: 6,
+                kind: SceneErrorKind::SingularTransform
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn camera_with_up_along_line_of_sight_is_an_error() {
+        let err = parse_scene(
+            "- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, 0]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+",
+        )
+        .unwrap_err();
+
+        assert_eq!(7, err.line);
+        assert_eq!(SceneErrorKind::SingularTransform, err.kind);
+    }
+
+    #[test]
+    fn recursive_definition_is_an_error() {
+        let err = parse_error("- define: t\n  value:\n    - t\n- add: sphere\n  transform: t\n");
+
+        assert_eq!(SceneErrorKind::Recursive("t".to_string()), err.kind);
+    }
+
+    #[test]
+    fn missing_key_is_reported_at_its_command() {
+        let err = parse_error("- add: light\n  at: [0, 0, 0]\n");
+
+        assert_eq!(
+            SceneError {
+                line: 9,
+                kind: SceneErrorKind::MissingKey("intensity")
+            },
+            err
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::ptr;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::transformation::Transform;
+use crate::vector::Vector;
+
+/// Common interface of all primitives that can be placed in a `World`.
+///
+/// Implementors only deal with object space: `local_intersect` receives a ray
+/// that has already been transformed by the inverse of `transform()`, and
+/// `local_normal_at` returns the normal at a point in object space.
+///
+/// Shapes are shared between render threads, so they must be `Send + Sync`.
+pub trait Shape: Any + Debug + Send + Sync {
+    fn transformation(&self) -> &Transform;
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible, as `Transform::new` does.
+    /// Check untrusted matrices with `Matrix4::invertible` first.
+    fn set_transform(&mut self, transform: Matrix4);
+    fn material(&self) -> &Material;
+    fn material_mut(&mut self) -> &mut Material;
+
+    /// Transforms of the groups this shape is nested in, if any
+    fn parent(&self) -> Option<&Parent>;
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>);
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+
+    /// Normal at `point` in object space.
+    ///
+    /// # Panics
+    ///
+    /// Groups and CSG nodes have no surface and panic here. Their hits refer
+    /// to the primitive that was hit, so only primitives are asked for normals.
+    fn local_normal_at(&self, point: Point) -> Vector;
+
+    /// Bounding box in object space
+    fn bounds(&self) -> BoundingBox;
+
+    /// Bounding box in the space of the enclosing group (or world)
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform())
+    }
+
+    /// Split shapes containing at least `threshold` children into a bounding
+    /// volume hierarchy. Primitives have nothing to divide.
+    fn divide(&mut self, _threshold: usize) {}
+
+    /// Whether `other` is this very shape or, for composite shapes, one of
+    /// the shapes inside it
+    fn includes(&self, other: &dyn Shape) -> bool {
+        ptr::addr_eq(self, other)
+    }
+
+    /// Normal at `point` for a hit with barycentric coordinates `u` and `v`.
+    /// Only shapes that interpolate normals need to override this.
+    fn local_normal_at_uv(&self, point: Point, _u: f64, _v: f64) -> Vector {
+        self.local_normal_at(point)
+    }
+
+    fn as_any(&self) -> &dyn Any;
+    fn shape_eq(&self, other: &dyn Shape) -> bool;
+
+    fn transform(&self) -> &Matrix4 {
+        self.transformation().matrix()
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let local_ray = ray.transform(self.transformation().inverse());
+        self.local_intersect(&local_ray)
+    }
+
+    fn normal_at(&self, world_point: Point) -> Vector {
+        let local_point = self.world_to_object(world_point);
+        self.normal_to_world(self.local_normal_at(local_point))
+    }
+
+    /// Like `normal_at`, but uses the u/v of `hit` when it has them
+    fn normal_at_hit(&self, world_point: Point, hit: &Intersection) -> Vector {
+        let local_point = self.world_to_object(world_point);
+        let local_normal = match hit.uv {
+            Some((u, v)) => self.local_normal_at_uv(local_point, u, v),
+            None => self.local_normal_at(local_point),
+        };
+        self.normal_to_world(local_normal)
+    }
+
+    fn world_to_object(&self, point: Point) -> Point {
+        let point = match self.parent() {
+            Some(parent) => parent.world_to_object(point),
+            None => point,
+        };
+        self.transformation().inverse() * point
+    }
+
+    fn normal_to_world(&self, normal: Vector) -> Vector {
+        let n = (self.transformation().inverse_transpose() * normal).normalize();
+        match self.parent() {
+            Some(parent) => parent.normal_to_world(n),
+            None => n,
+        }
+    }
+}
+
+/// Link in the chain of groups enclosing a shape: the transform of the
+/// immediate group and that group's own parent.
+#[derive(Debug, PartialEq)]
+pub struct Parent {
+    pub transform: Transform,
+    pub parent: Option<Arc<Parent>>,
+}
+
+impl Parent {
+    pub fn world_to_object(&self, point: Point) -> Point {
+        let point = match &self.parent {
+            Some(parent) => parent.world_to_object(point),
+            None => point,
+        };
+        self.transform.inverse() * point
+    }
+
+    pub fn normal_to_world(&self, normal: Vector) -> Vector {
+        let n = (self.transform.inverse_transpose() * normal).normalize();
+        match &self.parent {
+            Some(parent) => parent.normal_to_world(n),
+            None => n,
+        }
+    }
+}
+
+impl<'a> PartialEq for dyn Shape + 'a {
+    fn eq(&self, other: &(dyn Shape + 'a)) -> bool {
+        self.shape_eq(other)
+    }
+}
+
+/// Helper for implementing `Shape::shape_eq` for shapes that are `PartialEq`.
+pub fn shape_eq<T: Shape + PartialEq>(a: &T, b: &dyn Shape) -> bool {
+    b.as_any().downcast_ref::<T>().is_some_and(|b| a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transformation;
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct TestShape {
+        transform: Transform,
+        material: Material,
+        saved_ray: Mutex<Option<Ray>>,
+    }
+
+    impl PartialEq for TestShape {
+        fn eq(&self, other: &TestShape) -> bool {
+            self.transform == other.transform && self.material == other.material
+        }
+    }
+
+    impl TestShape {
+        fn new() -> TestShape {
+            TestShape {
+                transform: Transform::identity(),
+                material: Material::new(),
+                saved_ray: Mutex::new(None),
+            }
+        }
+    }
+
+    impl Shape for TestShape {
+        fn transformation(&self) -> &Transform {
+            &self.transform
+        }
+
+        fn set_transform(&mut self, transform: Matrix4) {
+            self.transform = Transform::new(transform);
+        }
+
+        fn material(&self) -> &Material {
+            &self.material
+        }
+
+        fn material_mut(&mut self) -> &mut Material {
+            &mut self.material
+        }
+
+        fn parent(&self) -> Option<&Parent> {
+            None
+        }
+
+        fn set_parent(&mut self, _parent: Option<Arc<Parent>>) {}
+
+        fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+            *self.saved_ray.lock().unwrap() = Some(*ray);
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: Point) -> Vector {
+            Vector::new(point.0, point.1, point.2)
+        }
+
+        fn bounds(&self) -> BoundingBox {
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn shape_eq(&self, other: &dyn Shape) -> bool {
+            shape_eq(self, other)
+        }
+    }
+
+    #[test]
+    fn intersecting_scaled_shape_with_ray() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::new();
+        s.set_transform(transformation::scaling(2.0, 2.0, 2.0));
+        s.intersect(&r);
+
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(Point::new(0.0, 0.0, -2.5), saved.origin);
+        assert_eq!(Vector::new(0.0, 0.0, 0.5), saved.direction);
+    }
+
+    #[test]
+    fn intersecting_translated_shape_with_ray() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::new();
+        s.set_transform(transformation::translation(5.0, 0.0, 0.0));
+        s.intersect(&r);
+
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(Point::new(-5.0, 0.0, -5.0), saved.origin);
+        assert_eq!(Vector::new(0.0, 0.0, 1.0), saved.direction);
+    }
+
+    #[test]
+    fn computing_normal_on_translated_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(transformation::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+
+        assert_eq!(Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), n);
+    }
+
+    #[test]
+    fn computing_normal_on_transformed_shape() {
+        let mut s = TestShape::new();
+        s.set_transform(
+            transformation::scaling(1.0, 0.5, 1.0) * transformation::rotation_z(PI / 5.0),
+        );
+        let n = s.normal_at(Point::new(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0));
+
+        assert_eq!(Vector::new(0.0, 0.97014, -0.24254), n);
+    }
+
+    #[test]
+    fn shapes_compare_by_type_and_value() {
+        let a = TestShape::new();
+        let b = TestShape::new();
+        let mut c = TestShape::new();
+        c.material.ambient = 1.0;
+
+        assert!(&a as &dyn Shape == &b as &dyn Shape);
+        assert!(&a as &dyn Shape != &c as &dyn Shape);
+    }
+}
@@ -1,26 +1,69 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
 use crate::intersection::Intersection;
 use crate::material::Material;
-use crate::matrix::Matrix;
+use crate::matrix::Matrix4;
+use crate::point::Point;
 use crate::ray::Ray;
-use crate::tuple::Tuple;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
 
 #[derive(Debug, PartialEq)]
 pub struct Sphere {
-    pub transform: Matrix,
+    transform: Transform,
     pub material: Material,
+    parent: Option<Arc<Parent>>,
 }
 
 impl Sphere {
     pub fn new() -> Sphere {
         Sphere {
-            transform: Matrix::identity(),
+            transform: Transform::identity(),
             material: Material::new(),
+            parent: None,
         }
     }
 
-    pub fn intersect(&self, orig_ray: &Ray) -> Vec<Intersection> {
-        let ray = orig_ray.transform(&self.transform.inverse());
-        let sphere_to_ray = ray.origin - Tuple::point(0.0, 0.0, 0.0);
+    /// Sphere with a glass-like transparent material
+    pub fn glass() -> Sphere {
+        let mut s = Sphere::new();
+        s.material.transparency = 1.0;
+        s.material.refractive_index = 1.5;
+        s
+    }
+}
+
+impl Shape for Sphere {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let sphere_to_ray = ray.origin - Point::new(0.0, 0.0, 0.0);
 
         let a = ray.direction.dot(ray.direction);
         let b = 2.0 * ray.direction.dot(sphere_to_ray);
@@ -37,16 +80,26 @@ impl Sphere {
         }
     }
 
-    pub fn normal_at(&self, world_point: Tuple) -> Tuple {
-        let object_point = self.transform.inverse().tuple_prod(world_point);
-        let object_normal = object_point - Tuple::point(0.0, 0.0, 0.0);
-        let world_normal = self
-            .transform
-            .inverse()
-            .transpose()
-            .tuple_prod(object_normal);
-        let world_normal2 = Tuple::vector(world_normal.0, world_normal.1, world_normal.2);
-        world_normal2.normalize()
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        point - Point::new(0.0, 0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+impl Default for Sphere {
+    fn default() -> Sphere {
+        Sphere::new()
     }
 }
 
@@ -54,16 +107,15 @@ impl Sphere {
 mod tests {
     use super::*;
     use crate::material::Material;
-    use crate::matrix::Matrix;
+    use crate::matrix::Matrix4;
     use crate::ray::Ray;
     use crate::transformation;
-    use crate::tuple::Tuple;
 
     use std::f64::consts::PI;
 
     #[test]
     fn ray_intersects_sphere_at_two_points() {
-        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Sphere::new();
         let xs = sphere.intersect(&ray);
 
@@ -74,7 +126,7 @@ mod tests {
 
     #[test]
     fn ray_intersects_sphere_at_tangent() {
-        let ray = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Sphere::new();
         let xs = sphere.intersect(&ray);
 
@@ -85,7 +137,7 @@ mod tests {
 
     #[test]
     fn ray_misses_sphere() {
-        let ray = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Sphere::new();
         let xs = sphere.intersect(&ray);
 
@@ -94,7 +146,7 @@ mod tests {
 
     #[test]
     fn ray_originates_inside_sphere() {
-        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Sphere::new();
         let xs = sphere.intersect(&ray);
 
@@ -105,7 +157,7 @@ mod tests {
 
     #[test]
     fn sphere_is_behind_ray() {
-        let ray = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
         let sphere = Sphere::new();
         let xs = sphere.intersect(&ray);
 
@@ -116,20 +168,20 @@ mod tests {
 
     #[test]
     fn intersect_sets_object_on_intersection() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let s = Sphere::new();
         let xs = s.intersect(&r);
 
         assert_eq!(2, xs.len());
-        assert_eq!(&s, xs[0].object);
-        assert_eq!(&s, xs[1].object);
+        assert_eq!(&s as &dyn Shape, xs[0].object);
+        assert_eq!(&s as &dyn Shape, xs[1].object);
     }
 
     #[test]
     fn spheres_default_transformation() {
         let s = Sphere::new();
 
-        assert_eq!(Matrix::identity(), s.transform);
+        assert_eq!(Matrix4::identity(), *s.transform());
     }
 
     #[test]
@@ -137,16 +189,16 @@ mod tests {
         let mut s = Sphere::new();
         let t = transformation::translation(2.0, 3.0, 4.0);
         let tt = transformation::translation(2.0, 3.0, 4.0); // TODO make matrices copyable
-        s.transform = t;
+        s.set_transform(t);
 
-        assert_eq!(tt, s.transform);
+        assert_eq!(tt, *s.transform());
     }
 
     #[test]
     fn intersecting_scaled_sphere_with_ray() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let mut s = Sphere::new();
-        s.transform = transformation::scaling(2.0, 2.0, 2.0);
+        s.set_transform(transformation::scaling(2.0, 2.0, 2.0));
         let xs = s.intersect(&r);
 
         assert_eq!(2, xs.len());
@@ -156,9 +208,9 @@ mod tests {
 
     #[test]
     fn intersecting_translated_sphere_with_ray() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let mut s = Sphere::new();
-        s.transform = transformation::translation(5.0, 0.0, 0.0);
+        s.set_transform(transformation::translation(5.0, 0.0, 0.0));
         let xs = s.intersect(&r);
 
         assert_eq!(0, xs.len());
@@ -167,38 +219,38 @@ mod tests {
     #[test]
     fn normal_on_sphere_at_point_on_x_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(1.0, 0.0, 0.0));
+        let n = s.normal_at(Point::new(1.0, 0.0, 0.0));
 
-        assert_eq!(Tuple::vector(1.0, 0.0, 0.0), n);
+        assert_eq!(Vector::new(1.0, 0.0, 0.0), n);
     }
 
     #[test]
     fn normal_on_sphere_at_point_on_y_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(0.0, 1.0, 0.0));
+        let n = s.normal_at(Point::new(0.0, 1.0, 0.0));
 
-        assert_eq!(Tuple::vector(0.0, 1.0, 0.0), n);
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), n);
     }
 
     #[test]
     fn normal_on_sphere_at_point_on_z_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(0.0, 0.0, 1.0));
+        let n = s.normal_at(Point::new(0.0, 0.0, 1.0));
 
-        assert_eq!(Tuple::vector(0.0, 0.0, 1.0), n);
+        assert_eq!(Vector::new(0.0, 0.0, 1.0), n);
     }
 
     #[test]
     fn normal_on_sphere_at_non_axial_point() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(
+        let n = s.normal_at(Point::new(
             f64::sqrt(3.0) / 3.0,
             f64::sqrt(3.0) / 3.0,
             f64::sqrt(3.0) / 3.0,
         ));
 
         assert_eq!(
-            Tuple::vector(
+            Vector::new(
                 f64::sqrt(3.0) / 3.0,
                 f64::sqrt(3.0) / 3.0,
                 f64::sqrt(3.0) / 3.0
@@ -210,7 +262,7 @@ mod tests {
     #[test]
     fn normal_is_normalized_vector() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(
+        let n = s.normal_at(Point::new(
             f64::sqrt(3.0) / 3.0,
             f64::sqrt(3.0) / 3.0,
             f64::sqrt(3.0) / 3.0,
@@ -220,25 +272,24 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_normal_on_translated_sphere() {
         let mut s = Sphere::new();
-        s.transform = transformation::translation(0.0, 1.0, 0.0);
-        let n = s.normal_at(Tuple::point(0.0, 1.70711, -0.70711));
+        s.set_transform(transformation::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(Point::new(0.0, 1.70711, -0.70711));
 
-        assert_eq!(Tuple::vector(0.0, 0.70711, -0.70711), n);
+        assert_eq!(Vector::new(0.0, 0.70711, -0.70711), n);
     }
 
     #[test]
     fn computing_normal_on_transformed_sphere() {
         let mut s = Sphere::new();
-        s.transform = transformation::scaling(1.0, 0.5, 1.0) * transformation::rotation_z(PI / 5.0);
-        let n = s.normal_at(Tuple::point(
-            0.0,
-            f64::sqrt(2.0) / 2.0,
-            -f64::sqrt(2.0) / 2.0,
-        ));
+        s.set_transform(
+            transformation::scaling(1.0, 0.5, 1.0) * transformation::rotation_z(PI / 5.0),
+        );
+        let n = s.normal_at(Point::new(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0));
 
-        assert_eq!(Tuple::vector(0.0, 0.97014, -0.24254), n);
+        assert_eq!(Vector::new(0.0, 0.97014, -0.24254), n);
     }
 
     #[test]
@@ -254,9 +305,37 @@ mod tests {
         let mut s = Sphere::new();
         let mut m = Material::new();
         m.ambient = 1.0;
-        s.material = m;
+        s.material = m.clone();
 
         assert_eq!(m, s.material);
     }
 
+    #[test]
+    fn helper_for_producing_sphere_with_glassy_material() {
+        let s = Sphere::glass();
+
+        assert_eq!(Matrix4::identity(), *s.transform());
+        assert_eq!(1.0, s.material.transparency);
+        assert_eq!(1.5, s.material.refractive_index);
+    }
+
+    #[test]
+    fn sphere_has_bounding_box() {
+        let b = Sphere::new().bounds();
+
+        assert_eq!(Point::new(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(Point::new(1.0, 1.0, 1.0), b.max);
+    }
+
+    #[test]
+    fn querying_shape_bounding_box_in_parent_space() {
+        let mut s = Sphere::new();
+        s.set_transform(
+            transformation::translation(1.0, -3.0, 5.0) * transformation::scaling(0.5, 2.0, 4.0),
+        );
+        let b = s.parent_space_bounds();
+
+        assert_eq!(Point::new(0.5, -5.0, 1.0), b.min);
+        assert_eq!(Point::new(1.5, -1.0, 9.0), b.max);
+    }
 }
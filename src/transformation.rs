@@ -1,323 +1,388 @@
-use crate::matrix::Matrix;
-use crate::tuple::Tuple;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::vector::Vector;
 
-pub fn translation(x: f64, y: f64, z: f64) -> Matrix {
+pub fn translation(x: f64, y: f64, z: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         1.0, 0.0, 0.0, x,
         0.0, 1.0, 0.0, y,
         0.0, 0.0, 1.0, z,
         0.0, 0.0, 0.0, 1.0,
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn scaling(x: f64, y: f64, z: f64) -> Matrix {
+pub fn scaling(x: f64, y: f64, z: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         x, 0.0, 0.0, 0.0,
         0.0, y, 0.0, 0.0,
         0.0, 0.0, z, 0.0,
         0.0, 0.0, 0.0, 1.0,
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn rotation_x(r: f64) -> Matrix {
+pub fn rotation_x(r: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         1.0, 0.0, 0.0, 0.0,
         0.0, r.cos(), -r.sin(), 0.0,
         0.0, r.sin(), r.cos(), 0.0,
         0.0, 0.0, 0.0, 1.0
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn rotation_y(r: f64) -> Matrix {
+pub fn rotation_y(r: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         r.cos(), 0.0, r.sin(), 0.0,
         0.0, 1.0, 0.0, 0.0,
         -r.sin(), 0.0, r.cos(), 0.0,
         0.0, 0.0, 0.0, 1.0
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn rotation_z(r: f64) -> Matrix {
+pub fn rotation_z(r: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         r.cos(), -r.sin(), 0.0, 0.0,
         r.sin(), r.cos(), 0.0, 0.0,
         0.0, 0.0, 1.0, 0.0,
         0.0, 0.0, 0.0, 1.0
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix {
+pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix4 {
     #[rustfmt::skip]
-    let v = vec![
+    let v = [
         1.0, xy, xz, 0.0,
         yx, 1.0, yz, 0.0,
         zx, zy, 1.0, 0.0,
         0.0, 0.0, 0.0, 1.0
     ];
-    Matrix::from_vector(4, &v)
+    Matrix4::new(v)
 }
 
-pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Matrix {
+pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix4 {
     let forward = (to - from).normalize();
     let left = forward.cross(up.normalize());
     let true_up = left.cross(forward);
 
     #[rustfmt::skip]
-    let elems = vec![
+    let elems = [
         left.0, left.1, left.2, 0.0,
 	true_up.0, true_up.1, true_up.2, 0.0,
 	-forward.0, -forward.1, -forward.2, 0.0,
 	0.0, 0.0, 0.0, 1.0,
     ];
-    let orientation = Matrix::from_vector(4, &elems);
+    let orientation = Matrix4::new(elems);
 
     orientation * translation(-from.0, -from.1, -from.2)
 }
 
+/// Transformation matrix stored together with its inverse and the
+/// transpose of the inverse, which are needed for every ray and normal
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+    inverse_transpose: Matrix4,
+}
+
+impl Transform {
+    /// # Panics
+    ///
+    /// Panics if `matrix` is not invertible; use `try_new` for matrices
+    /// that come from user input.
+    pub fn new(matrix: Matrix4) -> Transform {
+        Transform::try_new(matrix).expect("matrix is not invertible")
+    }
+
+    /// Transform for `matrix`, or `None` if it is not invertible
+    pub fn try_new(matrix: Matrix4) -> Option<Transform> {
+        let inverse = matrix.try_inverse()?;
+        let inverse_transpose = inverse.transpose();
+        Some(Transform {
+            matrix,
+            inverse,
+            inverse_transpose,
+        })
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(Matrix4::identity())
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix4 {
+        &self.inverse_transpose
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tuple::Tuple;
     use std::f64::consts::{PI, SQRT_2};
 
     #[test]
     fn multiplying_by_translation_matrix() {
         let transform = translation(5.0, -3.0, 2.0);
-        let p = Tuple::point(-3.0, 4.0, 5.0);
+        let p = Point::new(-3.0, 4.0, 5.0);
 
-        assert_eq!(Tuple::point(2.0, 1.0, 7.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(2.0, 1.0, 7.0), transform * p);
     }
 
     #[test]
     fn multiplying_by_inverse_of_translation_matrix() {
         let transform = translation(5.0, -3.0, 2.0);
         let inv = transform.inverse();
-        let p = Tuple::point(-3.0, 4.0, 5.0);
+        let p = Point::new(-3.0, 4.0, 5.0);
 
-        assert_eq!(Tuple::point(-8.0, 7.0, 3.0), inv.tuple_prod(p));
+        assert_eq!(Point::new(-8.0, 7.0, 3.0), inv * p);
     }
 
     #[test]
     fn translation_does_not_affect_vectors() {
         let transform = translation(5.0, -3.0, 2.0);
-        let v = Tuple::vector(-3.0, 4.0, 5.0);
+        let v = Vector::new(-3.0, 4.0, 5.0);
 
-        assert_eq!(v, transform.tuple_prod(v));
+        assert_eq!(v, transform * v);
     }
 
     #[test]
     fn scaling_matrix_applied_to_point() {
         let transform = scaling(2.0, 3.0, 4.0);
-        let p = Tuple::point(-4.0, 6.0, 8.0);
+        let p = Point::new(-4.0, 6.0, 8.0);
 
-        assert_eq!(Tuple::point(-8.0, 18.0, 32.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(-8.0, 18.0, 32.0), transform * p);
     }
 
     #[test]
     fn scaling_matrix_applied_to_vector() {
         let transform = scaling(2.0, 3.0, 4.0);
-        let v = Tuple::vector(-4.0, 6.0, 8.0);
+        let v = Vector::new(-4.0, 6.0, 8.0);
 
-        assert_eq!(Tuple::vector(-8.0, 18.0, 32.0), transform.tuple_prod(v));
+        assert_eq!(Vector::new(-8.0, 18.0, 32.0), transform * v);
     }
 
     #[test]
     fn multiplying_by_inverse_of_scaling_matrix() {
         let transform = scaling(2.0, 3.0, 4.0);
         let inv = transform.inverse();
-        let v = Tuple::vector(-4.0, 6.0, 8.0);
+        let v = Vector::new(-4.0, 6.0, 8.0);
 
-        assert_eq!(Tuple::vector(-2.0, 2.0, 2.0), inv.tuple_prod(v));
+        assert_eq!(Vector::new(-2.0, 2.0, 2.0), inv * v);
     }
 
     #[test]
     fn reflection_is_scaling_by_negative_value() {
         let transform = scaling(-1.0, 1.0, 1.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(-2.0, 3.0, 4.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(-2.0, 3.0, 4.0), transform * p);
     }
 
     #[test]
     fn rotating_point_around_x_axis() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_x(PI / 4.0);
         let full_quarter = rotation_x(PI / 2.0);
 
         assert_eq!(
-            Tuple::point(0.0, SQRT_2 / 2.0, SQRT_2 / 2.0),
-            half_quarter.tuple_prod(p)
+            Point::new(0.0, SQRT_2 / 2.0, SQRT_2 / 2.0),
+            half_quarter * p
         );
-        assert_eq!(Tuple::point(0.0, 0.0, 1.0), full_quarter.tuple_prod(p));
+        assert_eq!(Point::new(0.0, 0.0, 1.0), full_quarter * p);
     }
 
     #[test]
     fn inverse_of_x_rotation_rotates_in_opposite_direction() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_x(PI / 4.0);
         let inv = half_quarter.inverse();
 
-        assert_eq!(
-            Tuple::point(0.0, SQRT_2 / 2.0, -SQRT_2 / 2.0),
-            inv.tuple_prod(p)
-        );
+        assert_eq!(Point::new(0.0, SQRT_2 / 2.0, -SQRT_2 / 2.0), inv * p);
     }
 
     #[test]
     fn rotating_point_around_y_axis() {
-        let p = Tuple::point(0.0, 0.0, 1.0);
+        let p = Point::new(0.0, 0.0, 1.0);
         let half_quarter = rotation_y(PI / 4.0);
         let full_quarter = rotation_y(PI / 2.0);
 
         assert_eq!(
-            Tuple::point(SQRT_2 / 2.0, 0.0, SQRT_2 / 2.0),
-            half_quarter.tuple_prod(p)
+            Point::new(SQRT_2 / 2.0, 0.0, SQRT_2 / 2.0),
+            half_quarter * p
         );
-        assert_eq!(Tuple::point(1.0, 0.0, 0.0), full_quarter.tuple_prod(p));
+        assert_eq!(Point::new(1.0, 0.0, 0.0), full_quarter * p);
     }
 
     #[test]
     fn rotating_point_around_z_axis() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_z(PI / 4.0);
         let full_quarter = rotation_z(PI / 2.0);
 
         assert_eq!(
-            Tuple::point(-SQRT_2 / 2.0, SQRT_2 / 2.0, 0.0),
-            half_quarter.tuple_prod(p)
+            Point::new(-SQRT_2 / 2.0, SQRT_2 / 2.0, 0.0),
+            half_quarter * p
         );
-        assert_eq!(Tuple::point(-1.0, 0.0, 0.0), full_quarter.tuple_prod(p));
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), full_quarter * p);
     }
 
     #[test]
     fn shearing_transformation_moves_x_proportion_to_y() {
         let transform = shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(5.0, 3.0, 4.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(5.0, 3.0, 4.0), transform * p);
     }
 
     #[test]
     fn shearing_transformation_moves_x_proportion_to_z() {
         let transform = shearing(0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(6.0, 3.0, 4.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(6.0, 3.0, 4.0), transform * p);
     }
 
     #[test]
     fn shearing_transformation_moves_y_proportion_to_x() {
         let transform = shearing(0.0, 0.0, 1.0, 0.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(2.0, 5.0, 4.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(2.0, 5.0, 4.0), transform * p);
     }
 
     #[test]
     fn shearing_transformation_moves_y_proportion_to_z() {
         let transform = shearing(0.0, 0.0, 0.0, 1.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(2.0, 7.0, 4.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(2.0, 7.0, 4.0), transform * p);
     }
 
     #[test]
     fn shearing_transformation_moves_z_proportion_to_x() {
         let transform = shearing(0.0, 0.0, 0.0, 0.0, 1.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(2.0, 3.0, 6.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(2.0, 3.0, 6.0), transform * p);
     }
 
     #[test]
     fn shearing_transformation_moves_z_proportion_to_y() {
         let transform = shearing(0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
+        let p = Point::new(2.0, 3.0, 4.0);
 
-        assert_eq!(Tuple::point(2.0, 3.0, 7.0), transform.tuple_prod(p));
+        assert_eq!(Point::new(2.0, 3.0, 7.0), transform * p);
     }
 
     #[test]
     fn individual_transformations_are_applied_in_sequence() {
-        let p = Tuple::point(1.0, 0.0, 1.0);
+        let p = Point::new(1.0, 0.0, 1.0);
         let a = rotation_x(PI / 2.0);
         let b = scaling(5.0, 5.0, 5.0);
         let c = translation(10.0, 5.0, 7.0);
 
-        let p2 = a.tuple_prod(p);
-        assert_eq!(Tuple::point(1.0, -1.0, 0.0), p2);
+        let p2 = a * p;
+        assert_eq!(Point::new(1.0, -1.0, 0.0), p2);
 
-        let p3 = b.tuple_prod(p2);
-        assert_eq!(Tuple::point(5.0, -5.0, 0.0), p3);
+        let p3 = b * p2;
+        assert_eq!(Point::new(5.0, -5.0, 0.0), p3);
 
-        let p4 = c.tuple_prod(p3);
-        assert_eq!(Tuple::point(15.0, 0.0, 7.0), p4);
+        let p4 = c * p3;
+        assert_eq!(Point::new(15.0, 0.0, 7.0), p4);
     }
 
     #[test]
     fn chained_transformations_must_be_applied_in_reverse_order() {
-        let p = Tuple::point(1.0, 0.0, 1.0);
+        let p = Point::new(1.0, 0.0, 1.0);
         let a = rotation_x(PI / 2.0);
         let b = scaling(5.0, 5.0, 5.0);
         let c = translation(10.0, 5.0, 7.0);
 
         let t = c * b * a;
-        assert_eq!(Tuple::point(15.0, 0.0, 7.0), t.tuple_prod(p));
+        assert_eq!(Point::new(15.0, 0.0, 7.0), t * p);
     }
 
     #[test]
     fn transformation_matrix_for_default_orientation() {
-        let from = Tuple::point(0.0, 0.0, 0.0);
-        let to = Tuple::point(0.0, 0.0, -1.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
         let t = view_transform(from, to, up);
-        assert_eq!(Matrix::identity(), t);
+        assert_eq!(Matrix4::identity(), t);
     }
 
     #[test]
     fn view_transformation_matrix_looking_in_positive_z_direction() {
-        let from = Tuple::point(0.0, 0.0, 0.0);
-        let to = Tuple::point(0.0, 0.0, 1.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
         let t = view_transform(from, to, up);
         assert_eq!(scaling(-1.0, 1.0, -1.0), t);
     }
 
     #[test]
     fn view_transformation_moves_the_world() {
-        let from = Tuple::point(0.0, 0.0, 8.0);
-        let to = Tuple::point(0.0, 0.0, 0.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point::new(0.0, 0.0, 8.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
         let t = view_transform(from, to, up);
         assert_eq!(translation(0.0, 0.0, -8.0), t);
     }
 
     #[test]
     fn arbitrary_view_transformation() {
-        let from = Tuple::point(1.0, 3.0, 2.0);
-        let to = Tuple::point(4.0, -2.0, 8.0);
-        let up = Tuple::vector(1.0, 1.0, 0.0);
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
         let t = view_transform(from, to, up);
 
         #[rustfmt::skip]
-        let elems = vec![
+        let elems = [
             -0.50709, 0.50709,  0.67612, -2.36643,
 	     0.76772, 0.60609,  0.12122, -2.82843,
 	    -0.35857, 0.59761, -0.71714,  0.00000,
 	     0.00000, 0.00000,  0.00000,  1.00000,
         ];
-        let m = Matrix::from_vector(4, &elems);
+        let m = Matrix4::new(elems);
 
         assert_eq!(m, t);
     }
+
+    #[test]
+    fn transform_caches_inverse_and_inverse_transpose() {
+        let m = rotation_y(PI / 3.0) * translation(1.0, -2.0, 3.0) * scaling(2.0, 4.0, 0.5);
+        let t = Transform::new(m);
+
+        assert_eq!(m, *t.matrix());
+        assert_eq!(m.inverse(), *t.inverse());
+        assert_eq!(m.inverse().transpose(), *t.inverse_transpose());
+    }
+
+    #[test]
+    fn singular_matrix_has_no_transform() {
+        assert_eq!(None, Transform::try_new(scaling(1.0, 0.0, 1.0)));
+        assert!(Transform::try_new(scaling(0.01, 0.01, 0.01)).is_some());
+    }
 }
@@ -0,0 +1,376 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::shape;
+use crate::shape::{Parent, Shape};
+use crate::transformation::Transform;
+use crate::vector::Vector;
+use crate::EPSILON;
+
+/// Möller–Trumbore ray/triangle test, returns `t` and the barycentric
+/// `u` (towards p2) and `v` (towards p3) of the hit
+fn intersect_triangle(p1: Point, e1: Vector, e2: Vector, ray: &Ray) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = ray.direction.cross(e2);
+    let det = e1.dot(dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.direction.dot(origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
+}
+
+/// Flat shaded triangle
+#[derive(Debug, PartialEq)]
+pub struct Triangle {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    pub e1: Vector,
+    pub e2: Vector,
+    pub normal: Vector,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Triangle {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Triangle {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match intersect_triangle(self.p1, self.e1, self.e2, ray) {
+            Some((t, _, _)) => vec![Intersection::new(t, self)],
+            None => vec![],
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        self.normal
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+/// Triangle that interpolates the normals given at its vertices
+#[derive(Debug, PartialEq)]
+pub struct SmoothTriangle {
+    transform: Transform,
+    pub material: Material,
+    parent: Option<Arc<Parent>>,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    pub n1: Vector,
+    pub n2: Vector,
+    pub n3: Vector,
+    pub e1: Vector,
+    pub e2: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        n1: Vector,
+        n2: Vector,
+        n3: Vector,
+    ) -> SmoothTriangle {
+        SmoothTriangle {
+            transform: Transform::identity(),
+            material: Material::new(),
+            parent: None,
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn parent(&self) -> Option<&Parent> {
+        self.parent.as_deref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+        self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match intersect_triangle(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        // no hit available, so recover the barycentric coordinates from the point
+        let d = point - self.p1;
+        let d00 = self.e1.dot(self.e1);
+        let d01 = self.e1.dot(self.e2);
+        let d11 = self.e2.dot(self.e2);
+        let d20 = d.dot(self.e1);
+        let d21 = d.dot(self.e2);
+        let denom = d00 * d11 - d01 * d01;
+        let u = (d11 * d20 - d01 * d21) / denom;
+        let v = (d00 * d21 - d01 * d20) / denom;
+        self.local_normal_at_uv(point, u, v)
+    }
+
+    fn local_normal_at_uv(&self, _point: Point, u: f64, v: f64) -> Vector {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        shape::shape_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    fn smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_triangle() {
+        let t = triangle();
+
+        assert_eq!(Point::new(0.0, 1.0, 0.0), t.p1);
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), t.p2);
+        assert_eq!(Point::new(1.0, 0.0, 0.0), t.p3);
+        assert_eq!(Vector::new(-1.0, -1.0, 0.0), t.e1);
+        assert_eq!(Vector::new(1.0, -1.0, 0.0), t.e2);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), t.normal);
+    }
+
+    #[test]
+    fn finding_normal_on_triangle() {
+        let t = triangle();
+        let n1 = t.local_normal_at(Point::new(0.0, 0.5, 0.0));
+        let n2 = t.local_normal_at(Point::new(-0.5, 0.75, 0.0));
+        let n3 = t.local_normal_at(Point::new(0.5, 0.25, 0.0));
+
+        assert_eq!(t.normal, n1);
+        assert_eq!(t.normal, n2);
+        assert_eq!(t.normal, n3);
+    }
+
+    #[test]
+    fn intersecting_ray_parallel_to_triangle() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(0, t.local_intersect(&r).len());
+    }
+
+    #[test]
+    fn ray_misses_p1_p3_edge() {
+        let t = triangle();
+        let r = Ray::new(Point::new(1.0, 1.0, -2.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(0, t.local_intersect(&r).len());
+    }
+
+    #[test]
+    fn ray_misses_p1_p2_edge() {
+        let t = triangle();
+        let r = Ray::new(Point::new(-1.0, 1.0, -2.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(0, t.local_intersect(&r).len());
+    }
+
+    #[test]
+    fn ray_misses_p2_p3_edge() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(0, t.local_intersect(&r).len());
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert_eq!(2.0, xs[0].t);
+    }
+
+    #[test]
+    fn constructing_smooth_triangle() {
+        let tri = smooth_triangle();
+
+        assert_eq!(Point::new(0.0, 1.0, 0.0), tri.p1);
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), tri.p2);
+        assert_eq!(Point::new(1.0, 0.0, 0.0), tri.p3);
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), tri.n1);
+        assert_eq!(Vector::new(-1.0, 0.0, 0.0), tri.n2);
+        assert_eq!(Vector::new(1.0, 0.0, 0.0), tri.n3);
+    }
+
+    #[test]
+    fn intersection_with_smooth_triangle_stores_u_v() {
+        let tri = smooth_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = tri.local_intersect(&r);
+        let (u, v) = xs[0].uv.unwrap();
+
+        assert!((u - 0.45).abs() < EPSILON);
+        assert!((v - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn smooth_triangle_uses_u_v_to_interpolate_normal() {
+        let tri = smooth_triangle();
+        let i = Intersection::with_uv(1.0, &tri, 0.45, 0.25);
+        let n = tri.normal_at_hit(Point::new(0.0, 0.0, 0.0), &i);
+
+        assert_eq!(Vector::new(-0.5547, 0.83205, 0.0), n);
+    }
+
+    #[test]
+    fn smooth_triangle_normal_without_hit_matches_interpolated_normal() {
+        let tri = smooth_triangle();
+        let n = tri.normal_at(Point::new(-0.2, 0.3, 0.0));
+
+        assert_eq!(Vector::new(-0.5547, 0.83205, 0.0), n);
+    }
+
+    #[test]
+    fn triangle_has_bounding_box() {
+        let t = Triangle::new(
+            Point::new(-3.0, 7.0, 2.0),
+            Point::new(6.0, 2.0, -4.0),
+            Point::new(2.0, -1.0, -1.0),
+        );
+        let b = t.bounds();
+
+        assert_eq!(Point::new(-3.0, -1.0, -4.0), b.min);
+        assert_eq!(Point::new(6.0, 7.0, 2.0), b.max);
+    }
+}
@@ -1,9 +1,53 @@
+use std::error::Error;
+use std::fmt;
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Neg;
 use std::ops::Sub;
 
+/// What a tuple stands for, told apart by its `w`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TupleKind {
+    Point,
+    Vector,
+}
+
+impl TupleKind {
+    pub fn w(self) -> f64 {
+        match self {
+            TupleKind::Point => 1.0,
+            TupleKind::Vector => 0.0,
+        }
+    }
+}
+
+/// Tuple converted to a `Point` or `Vector` with the wrong `w`
+#[derive(Debug, PartialEq)]
+pub struct WrongKindError {
+    pub expected: TupleKind,
+    /// `w` of the converted tuple
+    pub found: f64,
+}
+
+impl fmt::Display for WrongKindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.expected {
+            TupleKind::Point => "point",
+            TupleKind::Vector => "vector",
+        };
+        write!(
+            f,
+            "a {} needs w = {}, found {}",
+            kind,
+            self.expected.w(),
+            self.found
+        )
+    }
+}
+
+impl Error for WrongKindError {}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Tuple(pub f64, pub f64, pub f64, pub f64);
 
@@ -342,5 +386,4 @@ mod tests {
 
         assert_eq!(Tuple::vector(1.0, 0.0, 0.0), r);
     }
-
 }
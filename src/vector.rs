@@ -0,0 +1,203 @@
+use std::convert::TryFrom;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::tuple::{Tuple, TupleKind, WrongKindError};
+use crate::EPSILON;
+
+/// Direction in space (a tuple with w = 0)
+#[derive(Copy, Clone, Debug)]
+pub struct Vector(pub f64, pub f64, pub f64);
+
+impl Vector {
+    pub fn new(x: f64, y: f64, z: f64) -> Vector {
+        Vector(x, y, z)
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Vector {
+        self / self.magnitude()
+    }
+
+    pub fn dot(self, other: Vector) -> f64 {
+        self.0 * other.0 + self.1 * other.1 + self.2 * other.2
+    }
+
+    pub fn cross(self, other: Vector) -> Vector {
+        Vector(
+            self.1 * other.2 - self.2 * other.1,
+            self.2 * other.0 - self.0 * other.2,
+            self.0 * other.1 - self.1 * other.0,
+        )
+    }
+
+    /// Reflect `self` around `normal`
+    pub fn reflect(self, normal: Vector) -> Vector {
+        self - normal * 2.0 * self.dot(normal)
+    }
+}
+
+impl PartialEq for Vector {
+    fn eq(&self, other: &Vector) -> bool {
+        (self.0 - other.0).abs() < EPSILON
+            && (self.1 - other.1).abs() < EPSILON
+            && (self.2 - other.2).abs() < EPSILON
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector(-self.0, -self.1, -self.2)
+    }
+}
+
+impl Mul<f64> for Vector {
+    type Output = Vector;
+
+    fn mul(self, rhs: f64) -> Vector {
+        Vector(self.0 * rhs, self.1 * rhs, self.2 * rhs)
+    }
+}
+
+impl Div<f64> for Vector {
+    type Output = Vector;
+
+    fn div(self, rhs: f64) -> Vector {
+        Vector(self.0 / rhs, self.1 / rhs, self.2 / rhs)
+    }
+}
+
+impl From<Vector> for Tuple {
+    fn from(v: Vector) -> Tuple {
+        Tuple::vector(v.0, v.1, v.2)
+    }
+}
+
+/// Fails unless `w` is 0, so that a point cannot pass for a vector
+impl TryFrom<Tuple> for Vector {
+    type Error = WrongKindError;
+
+    fn try_from(t: Tuple) -> Result<Vector, WrongKindError> {
+        if (t.3 - TupleKind::Vector.w()).abs() < EPSILON {
+            Ok(Vector(t.0, t.1, t.2))
+        } else {
+            Err(WrongKindError {
+                expected: TupleKind::Vector,
+                found: t.3,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtracting_two_vectors() {
+        let v1 = Vector::new(3.0, 2.0, 1.0);
+        let v2 = Vector::new(5.0, 6.0, 7.0);
+
+        assert_eq!(Vector::new(-2.0, -4.0, -6.0), v1 - v2);
+    }
+
+    #[test]
+    fn negating_vector() {
+        assert_eq!(Vector::new(-1.0, 2.0, -3.0), -Vector::new(1.0, -2.0, 3.0));
+    }
+
+    #[test]
+    fn multiplying_and_dividing_vector_by_scalar() {
+        let v = Vector::new(1.0, -2.0, 3.0);
+
+        assert_eq!(Vector::new(3.5, -7.0, 10.5), v * 3.5);
+        assert_eq!(Vector::new(0.5, -1.0, 1.5), v / 2.0);
+    }
+
+    #[test]
+    fn magnitude_of_vectors() {
+        assert_eq!(1.0, Vector::new(0.0, 1.0, 0.0).magnitude());
+        assert_eq!(14.0_f64.sqrt(), Vector::new(-1.0, -2.0, -3.0).magnitude());
+    }
+
+    #[test]
+    fn normalizing_vectors() {
+        let s = 14.0_f64.sqrt();
+
+        assert_eq!(
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(4.0, 0.0, 0.0).normalize()
+        );
+        assert_eq!(
+            Vector::new(1.0 / s, 2.0 / s, 3.0 / s),
+            Vector::new(1.0, 2.0, 3.0).normalize()
+        );
+    }
+
+    #[test]
+    fn dot_and_cross_products() {
+        let v1 = Vector::new(1.0, 2.0, 3.0);
+        let v2 = Vector::new(2.0, 3.0, 4.0);
+
+        assert_eq!(20.0, v1.dot(v2));
+        assert_eq!(Vector::new(-1.0, 2.0, -1.0), v1.cross(v2));
+        assert_eq!(Vector::new(1.0, -2.0, 1.0), v2.cross(v1));
+    }
+
+    #[test]
+    fn reflecting_vector_approaching_at_45() {
+        let v = Vector::new(1.0, -1.0, 0.0);
+        let n = Vector::new(0.0, 1.0, 0.0);
+
+        assert_eq!(Vector::new(1.0, 1.0, 0.0), v.reflect(n));
+    }
+
+    #[test]
+    fn reflecting_vector_off_slanted_surface() {
+        let v = Vector::new(0.0, -1.0, 0.0);
+        let n = Vector::new(f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0, 0.0);
+
+        assert_eq!(Vector::new(1.0, 0.0, 0.0), v.reflect(n));
+    }
+
+    #[test]
+    fn converting_vector_to_and_from_tuple() {
+        let t: Tuple = Vector::new(1.0, 2.0, 3.0).into();
+
+        assert_eq!(Tuple::vector(1.0, 2.0, 3.0), t);
+        assert_eq!(Ok(Vector::new(1.0, 2.0, 3.0)), Vector::try_from(t));
+    }
+
+    #[test]
+    fn converting_point_tuple_to_vector_fails() {
+        let err = Vector::try_from(Tuple::point(1.0, 2.0, 3.0)).unwrap_err();
+
+        assert_eq!(
+            WrongKindError {
+                expected: TupleKind::Vector,
+                found: 1.0
+            },
+            err
+        );
+    }
+}
@@ -1,52 +1,98 @@
+use crate::color::Color;
+use crate::group;
+use crate::group::Group;
 use crate::intersection;
 use crate::intersection::{Computations, Intersection};
 use crate::lights::PointLight;
 use crate::material::Material;
+use crate::point::Point;
 use crate::ray::Ray;
+use crate::shape::Shape;
 use crate::sphere::Sphere;
 use crate::transformation;
-use crate::tuple::Tuple;
 
+/// Default limit for recursive rays (e.g. reflections)
+pub const MAX_DEPTH: usize = 5;
+
+#[derive(Debug)]
 pub struct World {
-    light: Option<PointLight>,
-    objects: Vec<Sphere>,
+    pub lights: Vec<PointLight>,
+    pub objects: Vec<Box<dyn Shape>>,
+    pub max_depth: usize,
 }
 
 impl World {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> World {
         World {
-            light: None,
+            lights: vec![],
             objects: vec![],
+            max_depth: MAX_DEPTH,
         }
     }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> World {
         let mut s1 = Sphere::new();
         let m = Material {
-            color: Tuple::color(0.8, 1.0, 0.6),
+            color: Color::new(0.8, 1.0, 0.6),
             ambient: 0.1,
             shininess: 200.0,
             diffuse: 0.7,
             specular: 0.2,
+            ..Material::new()
         };
         s1.material = m;
 
         let mut s2 = Sphere::new();
         let t = transformation::scaling(0.5, 0.5, 0.5);
-        s2.transform = t;
+        s2.set_transform(t);
 
         World {
-            light: Some(PointLight::new(
-                Tuple::point(-10.0, 10.0, -10.0),
-                Tuple::color(1.0, 1.0, 1.0),
-            )),
-            objects: vec![s1, s2],
+            lights: vec![PointLight::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            objects: vec![Box::new(s1), Box::new(s2)],
+            max_depth: MAX_DEPTH,
         }
     }
-    pub fn contains(&self, s: &Sphere) -> bool {
-        self.objects.contains(s)
+
+    pub fn add_object<S: Shape>(&mut self, object: S) {
+        self.objects.push(Box::new(object));
+    }
+
+    pub fn add_light(&mut self, light: PointLight) {
+        self.lights.push(light);
+    }
+
+    /// Move every bounded object into a bounding volume hierarchy so that
+    /// rays only test the objects near them. Unbounded objects, like planes,
+    /// are left at the top level.
+    ///
+    /// `scene::parse_scene` does this for the worlds it loads; worlds built
+    /// in code should call it once all objects are added, before rendering.
+    pub fn build_bvh(&mut self) {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = self
+            .objects
+            .drain(..)
+            .partition(|o| o.parent_space_bounds().is_finite());
+        self.objects = unbounded;
+        if !bounded.is_empty() {
+            let mut root = Group::new();
+            for object in bounded {
+                root.add_boxed_child(object);
+            }
+            root.divide(group::DIVIDE_THRESHOLD);
+            self.objects.push(Box::new(root));
+        }
+    }
+
+    pub fn contains(&self, s: &dyn Shape) -> bool {
+        self.objects.iter().any(|o| o.as_ref() == s)
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         let vecs: Vec<_> = self.objects.iter().map(|x| x.intersect(ray)).collect();
         let mut vv = vec![];
         for v in vecs {
@@ -56,19 +102,82 @@ impl World {
         vv
     }
 
-    pub fn shade_hit(&self, c: Computations) -> Tuple {
-        c.object
-            .material
-            .lightning(self.light.unwrap(), c.point, c.eyev, c.normalv)
+    pub fn shade_hit(&self, c: Computations, remaining: usize) -> Color {
+        let material = c.object.material();
+        let surface = self
+            .lights
+            .iter()
+            .map(|&light| {
+                let shadowed = self.is_shadowed(light, c.over_point);
+                material.lightning(c.object, light, c.over_point, c.eyev, c.normalv, shadowed)
+            })
+            .fold(Color::new(0.0, 0.0, 0.0), |acc, c| acc + c);
+        let reflected = self.reflected_color(&c, remaining);
+        let refracted = self.refracted_color(&c, remaining);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = intersection::schlick(&c);
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    pub fn reflected_color(&self, c: &Computations, remaining: usize) -> Color {
+        let reflective = c.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let reflect_ray = Ray::new(c.over_point, c.reflectv);
+        self.color_at_depth(&reflect_ray, remaining - 1) * reflective
+    }
+
+    pub fn refracted_color(&self, c: &Computations, remaining: usize) -> Color {
+        let transparency = c.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        // Snell's law
+        let n_ratio = c.n1 / c.n2;
+        let cos_i = c.eyev.dot(c.normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            // total internal reflection
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = c.normalv * (n_ratio * cos_i - cos_t) - c.eyev * n_ratio;
+        let refract_ray = Ray::new(c.under_point, direction);
+        self.color_at_depth(&refract_ray, remaining - 1) * transparency
+    }
+
+    pub fn is_shadowed(&self, light: PointLight, point: Point) -> bool {
+        let v = light.position - point;
+        let distance = v.magnitude();
+        let r = Ray::new(point, v.normalize());
+        let xs = self.intersect(&r);
+
+        match intersection::hit(&xs) {
+            Some(h) => h.t < distance,
+            None => false,
+        }
     }
 
-    pub fn color_at(&self, r: &Ray) -> Tuple {
+    pub fn color_at(&self, r: &Ray) -> Color {
+        self.color_at_depth(r, self.max_depth)
+    }
+
+    /// Color seen by `r`, following at most `remaining` recursive rays
+    pub fn color_at_depth(&self, r: &Ray, remaining: usize) -> Color {
         let is = self.intersect(r);
         match intersection::hit(&is) {
-            None => Tuple::color(0.0, 0.0, 0.0),
+            None => Color::new(0.0, 0.0, 0.0),
             Some(i) => {
-                let comps = i.prepare_computations(r);
-                self.shade_hit(comps)
+                let comps = i.prepare_computations(r, &is);
+                self.shade_hit(comps, remaining)
             }
         }
     }
@@ -79,40 +188,41 @@ mod tests {
     use super::*;
     use crate::lights::PointLight;
     use crate::material::Material;
+    use crate::plane::Plane;
     use crate::ray::Ray;
     use crate::sphere::Sphere;
     use crate::transformation;
-    use crate::tuple::Tuple;
+    use crate::vector::Vector;
+
+    use std::f64::consts::SQRT_2;
 
     #[test]
     fn creating_world() {
         let w = World::new();
-        assert_eq!(None, w.light);
+        assert!(w.lights.is_empty());
         assert_eq!(0, w.objects.len())
     }
 
     #[test]
     fn default_world() {
-        let light = PointLight::new(
-            Tuple::point(-10.0, 10.0, -10.0),
-            Tuple::color(1.0, 1.0, 1.0),
-        );
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let mut s1 = Sphere::new();
         let m = Material {
-            color: Tuple::color(0.8, 1.0, 0.6),
+            color: Color::new(0.8, 1.0, 0.6),
             ambient: 0.1,
             shininess: 200.0,
             diffuse: 0.7,
             specular: 0.2,
+            ..Material::new()
         };
         s1.material = m;
 
         let mut s2 = Sphere::new();
         let t = transformation::scaling(0.5, 0.5, 0.5);
-        s2.transform = t;
+        s2.set_transform(t);
 
         let w = World::default();
-        assert_eq!(light, w.light.unwrap());
+        assert_eq!(vec![light], w.lights);
         assert!(w.contains(&s1));
         assert!(w.contains(&s2));
     }
@@ -120,7 +230,7 @@ mod tests {
     #[test]
     fn intersect_world_with_ray() {
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
         let xs = w.intersect(&r);
         assert_eq!(4, xs.len());
@@ -134,58 +244,468 @@ mod tests {
     fn shading_intersection() {
         // fail
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-        let shape = &w.objects[0];
-        let i = Intersection::new(4.0, &shape);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = w.objects[0].as_ref();
+        let i = Intersection::new(4.0, shape);
 
-        let comps = i.prepare_computations(&r);
-        let c = w.shade_hit(comps);
-        assert_eq!(Tuple::color(0.38066, 0.47583, 0.2855), c);
+        let comps = i.prepare_computations(&r, &[i]);
+        let c = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), c);
     }
 
     #[test]
     fn shading_intersection_from_inside() {
         let mut w = World::default();
-        w.light = Some(PointLight::new(
-            Tuple::point(0.0, 0.25, 0.0),
-            Tuple::color(1.0, 1.0, 1.0),
-        ));
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
-        let shape = &w.objects[1];
-        let i = Intersection::new(0.5, &shape);
+        w.lights = vec![PointLight::new(
+            Point::new(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )];
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = w.objects[1].as_ref();
+        let i = Intersection::new(0.5, shape);
 
-        let comps = i.prepare_computations(&r);
-        let c = w.shade_hit(comps);
-        assert_eq!(Tuple::color(0.90498, 0.90498, 0.90498), c);
+        let comps = i.prepare_computations(&r, &[i]);
+        let c = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.90498, 0.90498, 0.90498), c);
     }
 
     #[test]
     fn color_when_ray_misses() {
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
         let c = w.color_at(&r);
-        assert_eq!(Tuple::color(0.0, 0.0, 0.0), c);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c);
     }
 
     #[test]
     fn color_when_ray_hits() {
         // fail
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let c = w.color_at(&r);
-        assert_eq!(Tuple::color(0.38066, 0.47583, 0.2855), c);
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), c);
     }
 
     #[test]
     fn color_with_intersection_behind_ray() {
         let mut w = World::default();
-        let expected = w.objects[1].material.color;
-        let mut outer = &mut w.objects[0];
-        outer.material.ambient = 1.0;
-        let mut inner = &mut w.objects[1];
-        inner.material.ambient = 1.0;
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.75), Tuple::vector(0.0, 0.0, -1.0));
+        let expected = w.objects[1].material().color;
+        w.objects[0].material_mut().ambient = 1.0;
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
         let c = w.color_at(&r);
         assert_eq!(expected, c);
     }
+
+    #[test]
+    fn intersect_world_with_plane() {
+        let mut w = World::default();
+        let mut floor = Plane::new();
+        floor.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(floor);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+
+        let xs = w.intersect(&r);
+        assert_eq!(1, xs.len());
+        assert!((xs[0].t - SQRT_2).abs() < 1e-5);
+        assert_eq!(w.objects[2].as_ref(), xs[0].object);
+    }
+
+    #[test]
+    fn color_when_ray_hits_plane() {
+        let mut w = World::default();
+        let mut floor = Plane::new();
+        floor.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(floor);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+
+        let c = w.color_at(&r);
+        assert_eq!(Color::new(0.71755, 0.71755, 0.71755), c);
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let w = World::default();
+        let p = Point::new(0.0, 10.0, 0.0);
+
+        assert!(!w.is_shadowed(w.lights[0], p));
+    }
+
+    #[test]
+    fn shadow_when_object_is_between_point_and_light() {
+        let w = World::default();
+        let p = Point::new(10.0, -10.0, 10.0);
+
+        assert!(w.is_shadowed(w.lights[0], p));
+    }
+
+    #[test]
+    fn no_shadow_when_object_is_behind_light() {
+        let w = World::default();
+        let p = Point::new(-20.0, 20.0, -20.0);
+
+        assert!(!w.is_shadowed(w.lights[0], p));
+    }
+
+    #[test]
+    fn no_shadow_when_object_is_behind_point() {
+        let w = World::default();
+        let p = Point::new(-2.0, 2.0, -2.0);
+
+        assert!(!w.is_shadowed(w.lights[0], p));
+    }
+
+    #[test]
+    fn shade_hit_is_given_intersection_in_shadow() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_transform(transformation::translation(0.0, 0.0, 10.0));
+        w.add_object(s2);
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1].as_ref());
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let c = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.1, 0.1, 0.1), c);
+    }
+
+    #[test]
+    fn shading_intersection_with_two_lights() {
+        let mut w = World::default();
+        w.add_light(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let c = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855) * 2.0, c);
+    }
+
+    #[test]
+    fn each_light_has_its_own_shadow_test() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, 7.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_transform(transformation::translation(0.0, 0.0, 10.0));
+        w.add_object(s2);
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1].as_ref());
+
+        // the first light is blocked by the sphere at the origin and gives
+        // only ambient, the second one shines straight on the surface and
+        // adds full diffuse and specular on top of its ambient
+        let comps = i.prepare_computations(&r, &[i]);
+        let c = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(2.0, 2.0, 2.0), c);
+    }
+
+    #[test]
+    fn world_without_lights_renders_black() {
+        let mut w = World::default();
+        w.lights.clear();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let c = w.color_at(&r);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c);
+    }
+
+    #[test]
+    fn reflected_color_for_nonreflective_material() {
+        let mut w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        w.objects[1].material_mut().ambient = 1.0;
+        let shape = w.objects[1].as_ref();
+        let i = Intersection::new(1.0, shape);
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let color = w.reflected_color(&comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), color);
+    }
+
+    #[test]
+    fn reflected_color_for_reflective_material() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.material.reflective = 0.5;
+        shape.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(shape);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+        let i = Intersection::new(SQRT_2, w.objects[2].as_ref());
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let color = w.reflected_color(&comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.19033, 0.23792, 0.14275), color);
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_material() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.material.reflective = 0.5;
+        shape.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(shape);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+        let i = Intersection::new(SQRT_2, w.objects[2].as_ref());
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let color = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.87676, 0.92434, 0.82917), color);
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut lower = Plane::new();
+        lower.material.reflective = 1.0;
+        lower.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(lower);
+        let mut upper = Plane::new();
+        upper.material.reflective = 1.0;
+        upper.set_transform(transformation::translation(0.0, 1.0, 0.0));
+        w.add_object(upper);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        // terminates instead of overflowing the stack, each of the
+        // MAX_DEPTH + 1 hits contributes fully lit white
+        let c = w.color_at(&r);
+        assert_eq!(Color::new(1.9, 1.9, 1.9) * (MAX_DEPTH + 1) as f64, c);
+    }
+
+    #[test]
+    fn reflected_color_at_maximum_recursive_depth() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.material.reflective = 0.5;
+        shape.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(shape);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+        let i = Intersection::new(SQRT_2, w.objects[2].as_ref());
+
+        let comps = i.prepare_computations(&r, &[i]);
+        let color = w.reflected_color(&comps, 0);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), color);
+    }
+
+    #[test]
+    fn max_depth_is_configurable() {
+        let mut w = World::new();
+        w.max_depth = 1;
+        w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut lower = Plane::new();
+        lower.material.reflective = 1.0;
+        lower.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        w.add_object(lower);
+        let mut upper = Plane::new();
+        upper.material.reflective = 1.0;
+        upper.set_transform(transformation::translation(0.0, 1.0, 0.0));
+        w.add_object(upper);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        // the upper plane and a single reflection of the lower one
+        let c = w.color_at(&r);
+        assert_eq!(Color::new(3.8, 3.8, 3.8), c);
+    }
+
+    #[test]
+    fn refracted_color_with_opaque_surface() {
+        let w = World::default();
+        let shape = w.objects[0].as_ref();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = intersection::intersections(&[
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let c = w.refracted_color(&comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c);
+    }
+
+    #[test]
+    fn refracted_color_at_maximum_recursive_depth() {
+        let mut w = World::default();
+        let m = w.objects[0].material_mut();
+        m.transparency = 1.0;
+        m.refractive_index = 1.5;
+        let shape = w.objects[0].as_ref();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = intersection::intersections(&[
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let c = w.refracted_color(&comps, 0);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c);
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = World::default();
+        let m = w.objects[0].material_mut();
+        m.transparency = 1.0;
+        m.refractive_index = 1.5;
+        let shape = w.objects[0].as_ref();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, SQRT_2 / 2.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = intersection::intersections(&[
+            Intersection::new(-SQRT_2 / 2.0, shape),
+            Intersection::new(SQRT_2 / 2.0, shape),
+        ]);
+
+        // inside the sphere, so look at the second intersection
+        let comps = xs[1].prepare_computations(&r, &xs);
+        let c = w.refracted_color(&comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c);
+    }
+
+    #[test]
+    fn refracted_color_with_refracted_ray() {
+        let mut w = World::default();
+        w.objects[0].material_mut().ambient = 1.0;
+        let m = w.objects[1].material_mut();
+        m.transparency = 1.0;
+        m.refractive_index = 1.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+
+        // with matching indices the ray passes straight through the inner
+        // sphere, picking up its ambient term on the way out, and then sees
+        // the inside of the fully ambient outer sphere
+        let comps = xs[1].prepare_computations(&r, &xs);
+        let c = w.refracted_color(&comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.9, 1.1, 0.7), c);
+    }
+
+    fn world_with_glass_floor(reflective: f64) -> World {
+        let mut w = World::default();
+        let mut floor = Plane::new();
+        floor.set_transform(transformation::translation(0.0, -1.0, 0.0));
+        floor.material.reflective = reflective;
+        floor.material.transparency = 0.5;
+        floor.material.refractive_index = 1.5;
+        w.add_object(floor);
+        let mut ball = Sphere::new();
+        ball.material.color = Color::new(1.0, 0.0, 0.0);
+        ball.material.ambient = 0.5;
+        ball.set_transform(transformation::translation(0.0, -3.5, -0.5));
+        w.add_object(ball);
+        w
+    }
+
+    #[test]
+    fn shade_hit_with_transparent_material() {
+        let w = world_with_glass_floor(0.0);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+        let xs = intersection::intersections(&[Intersection::new(SQRT_2, w.objects[2].as_ref())]);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let color = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.93642, 0.68642, 0.68642), color);
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_transparent_material() {
+        let w = world_with_glass_floor(0.5);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -SQRT_2 / 2.0, SQRT_2 / 2.0),
+        );
+        let xs = intersection::intersections(&[Intersection::new(SQRT_2, w.objects[2].as_ref())]);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let color = w.shade_hit(comps, MAX_DEPTH);
+        assert_eq!(Color::new(0.93391, 0.69643, 0.69243), color);
+    }
+
+    #[test]
+    fn building_bvh_keeps_unbounded_objects_at_top_level() {
+        let mut w = World::default();
+        w.add_object(Plane::new());
+        w.build_bvh();
+
+        assert_eq!(2, w.objects.len());
+        assert!(w.objects[0].as_ref() == &Plane::new() as &dyn Shape);
+        assert!(w.objects[1].as_any().is::<Group>());
+    }
+
+    #[test]
+    fn building_bvh_does_not_change_colors() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        for i in 0..20 {
+            let mut s = Sphere::new();
+            s.set_transform(
+                transformation::translation(
+                    (i % 5) as f64 * 2.0 - 4.0,
+                    (i / 5) as f64 * 2.0 - 3.0,
+                    0.0,
+                ) * transformation::scaling(0.8, 0.8, 0.8),
+            );
+            s.material.color = Color::new(0.05 * i as f64, 0.5, 1.0 - 0.05 * i as f64);
+            w.add_object(s);
+        }
+        let mut floor = Plane::new();
+        floor.set_transform(transformation::translation(0.0, -4.0, 0.0));
+        w.add_object(floor);
+
+        let rays: Vec<Ray> = (0..40)
+            .map(|i| {
+                let target = Point::new(i as f64 * 0.25 - 5.0, i as f64 * 0.2 - 4.5, 0.0);
+                let origin = Point::new(0.0, 0.0, -10.0);
+                Ray::new(origin, (target - origin).normalize())
+            })
+            .collect();
+        let before: Vec<Color> = rays.iter().map(|r| w.color_at(r)).collect();
+        w.build_bvh();
+        let after: Vec<Color> = rays.iter().map(|r| w.color_at(r)).collect();
+
+        assert_eq!(before, after);
+    }
 }